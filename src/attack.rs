@@ -0,0 +1,128 @@
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// Everything about a piece lock that may be worth some attack.
+#[derive(Clone, Copy, Debug)]
+pub struct Clear {
+    pub lines: usize,
+    pub t_spin: TSpin,
+    // Clears in a row before this one, 0 for the first of a chain.
+    pub combo: u32,
+    // Difficult clears in a row before this one, 0 without back to back.
+    pub back_to_back: u32,
+    pub perfect_clear: bool,
+}
+
+impl Clear {
+    // Tetrises and line clearing T-spins keep the back to back chain going.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (TSpin::None != self.t_spin && self.lines > 0)
+    }
+}
+
+pub trait AttackTable: AttackTableClone {
+    fn attack(&self, clear: &Clear) -> u32;
+}
+
+pub trait AttackTableClone {
+    fn clone_box(&self) -> Box<dyn AttackTable>;
+}
+
+impl<T: 'static + AttackTable + Clone> AttackTableClone for T {
+    fn clone_box(&self) -> Box<dyn AttackTable> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn AttackTable> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+pub fn table_from_name(name: &str) -> Option<Box<dyn AttackTable>> {
+    match name {
+        "guideline" => Some(Box::new(Guideline)),
+        "tetrio" => Some(Box::new(TetrioLike)),
+        "classic" => Some(Box::new(Classic)),
+        _ => None,
+    }
+}
+
+const GUIDELINE_COMBO: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const PERFECT_CLEAR: u32 = 10;
+
+fn base_attack(clear: &Clear) -> u32 {
+    match (clear.t_spin, clear.lines) {
+        (_, 0) => 0,
+        (TSpin::None, 1) => 0,
+        (TSpin::None, 2) => 1,
+        (TSpin::None, 3) => 2,
+        (TSpin::None, _) => 4,
+        (TSpin::Mini, 1) => 0,
+        (TSpin::Mini, _) => 1,
+        (TSpin::Full, lines) => 2 * lines.min(3) as u32,
+    }
+}
+
+fn perfect_clear_bonus(clear: &Clear) -> u32 {
+    if clear.perfect_clear { PERFECT_CLEAR } else { 0 }
+}
+
+// Modern guideline: flat back to back bonus and a combo table.
+#[derive(Clone)]
+pub struct Guideline;
+
+impl AttackTable for Guideline {
+    fn attack(&self, clear: &Clear) -> u32 {
+        if 0 == clear.lines {
+            return 0;
+        }
+        let back_to_back = if clear.back_to_back > 0 { 1 } else { 0 };
+        let combo = GUIDELINE_COMBO[(clear.combo as usize).min(GUIDELINE_COMBO.len() - 1)];
+        base_attack(clear) + back_to_back + combo + perfect_clear_bonus(clear)
+    }
+}
+
+// Back to back bonus growing with the chain and combos multiplying the
+// attack, after TETR.IO.
+#[derive(Clone)]
+pub struct TetrioLike;
+
+impl AttackTable for TetrioLike {
+    fn attack(&self, clear: &Clear) -> u32 {
+        if 0 == clear.lines {
+            return 0;
+        }
+        let mut attack = base_attack(clear) as f64;
+        if clear.back_to_back > 0 {
+            attack += (1. + (1. + 0.8 * clear.back_to_back as f64).ln()).floor();
+        }
+        let combo = clear.combo as f64;
+        attack = if attack > 0. {
+            attack * (1. + 0.25 * combo)
+        } else {
+            (1. + 1.25 * combo).ln()
+        };
+        attack.floor() as u32 + perfect_clear_bonus(clear)
+    }
+}
+
+// Only the number of lines counts.
+#[derive(Clone)]
+pub struct Classic;
+
+impl AttackTable for Classic {
+    fn attack(&self, clear: &Clear) -> u32 {
+        match clear.lines {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            _ => 4,
+        }
+    }
+}
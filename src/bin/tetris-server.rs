@@ -0,0 +1,192 @@
+//! Match server for online versus, meant for playing from several browser
+//! tabs on one machine:
+//!
+//!     cargo run --features server --bin tetris-server -- 127.0.0.1:9001
+//!
+//! Players joining the same room are paired. Once both are ready the server
+//! deals a seed, then relays inputs between them until one knocks out.
+
+use std::collections::HashMap;
+use std::env;
+use std::io;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::thread;
+use std::time::Duration;
+
+use rand::Rng;
+use tetris::protocol::Packet;
+use tungstenite::Message;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9001";
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+#[derive(Default)]
+struct Room {
+    players: [Option<mpsc::Sender<Packet>>; 2],
+    ready: [bool; 2],
+}
+
+impl Room {
+    // A player whose connection is gone is dropped from the room, the
+    // connection leaving it on its own otherwise.
+    fn send(&mut self, player: usize, packet: Packet) {
+        let sent = match &self.players[player] {
+            Some(sender) => sender.send(packet).is_ok(),
+            None => return,
+        };
+        if !sent {
+            self.players[player] = None;
+        }
+    }
+
+    fn broadcast(&mut self, packet: Packet) {
+        for player in 0..self.players.len() {
+            self.send(player, packet.clone());
+        }
+    }
+
+    // The player left standing wins.
+    fn knock_out(&mut self, player: usize) {
+        self.broadcast(Packet::KnockOut {player: player as u8});
+        self.broadcast(Packet::Results {winner: (1 - player) as u8});
+        self.ready = [false; 2];
+    }
+}
+
+type Rooms = Arc<Mutex<HashMap<String, Room>>>;
+
+// A connection panicking with the rooms locked must not take the others
+// down with it.
+fn lock(rooms: &Rooms) -> MutexGuard<'_, HashMap<String, Room>> {
+    rooms.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn main() {
+    let address = env::args().nth(1).unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+    let listener = TcpListener::bind(&address).expect("could not bind the server address");
+    println!("listening on ws://{}", address);
+
+    let rooms: Rooms = Arc::new(Mutex::new(HashMap::new()));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("connection failed: {}", error);
+                continue;
+            },
+        };
+        let rooms = Arc::clone(&rooms);
+        thread::spawn(move || {
+            if let Err(error) = serve(stream, rooms) {
+                eprintln!("connection closed: {}", error);
+            }
+        });
+    }
+}
+
+// A connection alternates between reading its socket, with a short
+// timeout, and writing what the other player's connection queued for it.
+// Errors are boxed, tungstenite's own being rather large.
+fn serve(stream: TcpStream, rooms: Rooms) -> Result<(), Box<tungstenite::Error>> {
+    let mut socket = tungstenite::accept(stream).map_err(|error| match error {
+        tungstenite::HandshakeError::Failure(error) => Box::new(error),
+        tungstenite::HandshakeError::Interrupted(_) => Box::new(tungstenite::Error::ConnectionClosed),
+    })?;
+    socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)).map_err(|error| Box::new(error.into()))?;
+    let (sender, receiver) = mpsc::channel::<Packet>();
+    let mut seat: Option<(String, usize)> = None;
+
+    let result = 'connection: loop {
+        for packet in receiver.try_iter() {
+            if let Err(error) = socket.send(Message::Binary(packet.encode())) {
+                break 'connection Err(Box::new(error));
+            }
+        }
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(tungstenite::Error::Io(error)) if is_timeout(&error) => continue,
+            Err(error) => break Err(Box::new(error)),
+        };
+        let bytes = match message {
+            Message::Binary(bytes) => bytes,
+            Message::Close(_) => break Ok(()),
+            _ => continue,
+        };
+        let packet = match Packet::decode(&bytes) {
+            Ok(packet) => packet,
+            Err(error) => {
+                eprintln!("dropped packet: {}", error);
+                continue;
+            },
+        };
+        let mut rooms = lock(&rooms);
+        let (packet, room, player) = match (packet, &seat) {
+            (Packet::Join {room, name}, None) => {
+                let players = &mut rooms.entry(room.clone()).or_default().players;
+                let player = match players.iter().position(|player| player.is_none()) {
+                    None => {
+                        eprintln!("{} can not join full room {}", name, room);
+                        break Ok(());
+                    },
+                    Some(player) => player,
+                };
+                println!("{} joins room {} as player {}", name, room, player);
+                players[player] = Some(sender.clone());
+                // Queued for this very connection, whose receiver is alive.
+                let _ = sender.send(Packet::Joined {player: player as u8});
+                seat = Some((room, player));
+                continue;
+            },
+            (packet, Some((room_name, player))) => match rooms.get_mut(room_name) {
+                Some(room) => (packet, room, *player),
+                None => {
+                    eprintln!("room {} is gone", room_name);
+                    break Ok(());
+                },
+            },
+            (packet, None) => {
+                eprintln!("unexpected packet: {:?}", packet);
+                continue;
+            },
+        };
+        match packet {
+            Packet::Ready => {
+                room.ready[player] = true;
+                if room.ready.iter().all(|&ready| ready) {
+                    room.broadcast(Packet::Start {seed: rand::thread_rng().gen()});
+                }
+            },
+            Packet::Inputs {start_frame, inputs, ack, ..} => {
+                room.send(1 - player, Packet::Inputs {player: player as u8, start_frame, inputs, ack});
+            },
+            Packet::KnockOut {..} => room.knock_out(player),
+            packet => eprintln!("unexpected packet: {:?}", packet),
+        }
+    };
+
+    // A player leaving a match in progress loses it.
+    if let Some((room_name, player)) = seat {
+        let mut rooms = lock(&rooms);
+        let room = match rooms.get_mut(&room_name) {
+            Some(room) => room,
+            None => return result,
+        };
+        room.players[player] = None;
+        if room.players.iter().all(|player| player.is_none()) {
+            rooms.remove(&room_name);
+        } else if room.ready.iter().all(|&ready| ready) {
+            room.knock_out(player);
+        }
+    }
+    result
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
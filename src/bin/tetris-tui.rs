@@ -0,0 +1,325 @@
+//! Terminal frontend, to play and debug without a browser:
+//!
+//!     cargo run --features tui --bin tetris-tui -- [mode]
+//!
+//! Arrows move and rotate, space pauses, `u` and `r` undo and redo, `q` or
+//! Ctrl+C quits. The game has no hold piece, so none is shown.
+//!
+//! `s` saves a screenshot to `tetris-<milliseconds>.png` and `g` the game so far,
+//! since the last undo or redo, to `tetris-replay.gif`.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use tetris::draw;
+use tetris::draw::Renderer;
+use tetris::game::Game;
+use tetris::game::State;
+use tetris::game_loop::FixedTimestep;
+use tetris::game_loop::TICKS_PER_SECOND;
+use tetris::input;
+use tetris::input::Input;
+use tetris::mode;
+use tetris::raster;
+use tetris::replay::Replay;
+use tetris::theme;
+use tetris::theme::Theme;
+
+const CTRL_C: u8 = 0x03;
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+// Ten images per second.
+const REPLAY_FRAMES_PER_IMAGE: usize = 6;
+
+// Puts the terminal in raw mode, without echo nor line buffering, until
+// dropped. Ctrl+C comes as a key rather than as a signal, which would end
+// the program without restoring the terminal.
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        // Reads return right away, with whatever was typed.
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        print!("\x1b[?25l\x1b[2J");
+        Ok(RawMode {original})
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+        println!("\x1b[0m\x1b[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Character(u8),
+}
+
+// Arrows come as `ESC [ A` to `ESC [ D`.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index..] {
+            [0x1b, b'[', arrow, ..] => {
+                match arrow {
+                    b'A' => keys.push(Key::Up),
+                    b'B' => keys.push(Key::Down),
+                    b'C' => keys.push(Key::Right),
+                    b'D' => keys.push(Key::Left),
+                    _ => {},
+                }
+                index += 3;
+            },
+            [byte, ..] => {
+                keys.push(Key::Character(byte));
+                index += 1;
+            },
+            [] => break,
+        }
+    }
+    keys
+}
+
+// Draws frames in memory, two characters per cell, to be printed side by
+// side.
+#[derive(Default)]
+struct TerminalRenderer {
+    columns: usize,
+    // Top row first, empty cells are empty strings.
+    cells: Vec<Vec<String>>,
+    // Rows from this one up are above the board.
+    hidden_from: Option<usize>,
+    texts: Vec<(usize, String)>,
+}
+
+impl TerminalRenderer {
+    fn lines(&self) -> Vec<String> {
+        let rows = self.cells.len();
+        let mut lines: Vec<String> = self.cells.iter().enumerate()
+            .map(|(index, row)| {
+                let hidden = self.hidden_from.is_some_and(|hidden_from| rows - 1 - index >= hidden_from);
+                row.iter()
+                    .map(|cell| match cell.as_str() {
+                        "" if hidden => "  ".to_string(),
+                        "" => "\x1b[90m .\x1b[0m".to_string(),
+                        _ => format!("{}\x1b[0m", cell),
+                    })
+                    .collect()
+            })
+            .collect();
+        for (row, text) in self.texts.iter() {
+            let width = self.columns * 2;
+            let text: String = text.chars().take(width).collect();
+            let padding = width - text.chars().count();
+            lines[rows - 1 - row] = format!(
+                "\x1b[1;7m{}{}{}\x1b[0m",
+                " ".repeat(padding / 2),
+                text,
+                " ".repeat(padding - padding / 2),
+            );
+        }
+        lines
+    }
+}
+
+// A cell in the color of the theme, the ghost shaded, the empty ones left
+// to `TerminalRenderer::lines`.
+fn ansi_cell(color: &str, theme: &Theme) -> String {
+    if theme.empty == color {
+        return String::new();
+    }
+    let [red, green, blue] = theme::rgb(color).unwrap_or([0xFF, 0xFF, 0xFF]);
+    let shade = if theme.ghost == color { "░░" } else { "██" };
+    format!("\x1b[38;2;{};{};{}m{}", red, green, blue, shade)
+}
+
+impl Renderer for TerminalRenderer {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.columns = columns;
+        self.cells = vec![vec![String::new(); columns]; rows];
+        self.hidden_from = None;
+        self.texts.clear();
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        let rows = self.cells.len();
+        if row >= rows || column >= self.columns {
+            return;
+        }
+        self.cells[rows - 1 - row][column] = ansi_cell(color, self.theme());
+    }
+
+    // Dots on the empty cells stand for the grid.
+    fn grid(&mut self, _color: &str) {}
+
+    fn horizontal_line(&mut self, row: usize, _color: &str) {
+        self.hidden_from = Some(row);
+    }
+
+    fn text(&mut self, row: usize, text: &str, _color: &str) {
+        self.texts.push((row, text.to_string()));
+    }
+}
+
+// Below the board, what became of the last screenshot or replay saved.
+fn render(game: &Game, board: &mut TerminalRenderer, next: &mut TerminalRenderer, status: &str) -> String {
+    draw::draw_board_with_ghost(game, board);
+    draw::draw_next_pieces(game, next);
+
+    let mut side = vec![String::from("NEXT")];
+    side.extend(next.lines());
+    side.push(String::new());
+    side.push(format!("Score  {}", game.score()));
+    side.push(format!("Lines  {}", game.lines()));
+    side.push(format!("Level  {}", game.level()));
+    side.push(format!("Time   {}", mode::format_time(game.elapsed_ms())));
+    side.push(format!("PPS    {:.2}", mode::pieces_per_second(game)));
+    side.push(format!("APM    {:.1}", mode::attack_per_minute(game)));
+    if let State::Over | State::Finished = game.state() {
+        side.push(String::new());
+        side.extend(game.report().iter().map(|(label, value)| format!("{:<7}{}", label, value)));
+    }
+
+    let mut frame = String::from("\x1b[H");
+    for (index, line) in board.lines().iter().enumerate() {
+        frame.push('│');
+        frame.push_str(line);
+        frame.push_str("│  ");
+        frame.push_str(side.get(index).map_or("", String::as_str));
+        frame.push_str("\x1b[K\n");
+    }
+    frame.push('└');
+    frame.push_str(&"──".repeat(board.columns));
+    frame.push_str("┘\x1b[K\n");
+    frame.push_str(status);
+    frame.push_str("\x1b[K\n");
+    frame
+}
+
+// Presses of different keys are played on the same frame, a key pressed
+// again waits for the next one so that no press is lost.
+fn next_frame_input(presses: &mut VecDeque<Input>) -> Input {
+    let mut input = Input::NONE;
+    while let Some(&press) = presses.front() {
+        if input.contains(press) {
+            break;
+        }
+        input |= press;
+        presses.pop_front();
+    }
+    input
+}
+
+// Writes `bytes` to `path`, telling how it went.
+fn save(path: &str, bytes: &[u8]) -> String {
+    match fs::write(path, bytes) {
+        Ok(()) => format!("saved {}", path),
+        Err(error) => format!("could not save {}: {}", path, error),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut game = match std::env::args().nth(1) {
+        None => {
+            let mut game = Game::new();
+            game.allow_undo();
+            game
+        },
+        Some(name) => match mode::from_name(&name) {
+            Some(mode) => Game::with_mode(mode),
+            None => {
+                eprintln!("unknown mode {}", name);
+                std::process::exit(1);
+            },
+        },
+    };
+
+    let _raw_mode = RawMode::enable()?;
+    let mut stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let mut board = TerminalRenderer::default();
+    let mut next = TerminalRenderer::default();
+    let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+    let start = Instant::now();
+    let mut input = [0; 64];
+    game.run();
+    let mut replay = Replay::new(game.clone());
+    // Moves are played on the next frames, the way the replay plays them.
+    let mut presses = VecDeque::new();
+    let mut status = String::new();
+
+    loop {
+        let read = stdin.read(&mut input)?;
+        for key in parse_keys(&input[..read]) {
+            match key {
+                Key::Up => presses.push_back(Input::ROTATE),
+                Key::Down => presses.push_back(Input::DOWN),
+                Key::Left => presses.push_back(Input::LEFT),
+                Key::Right => presses.push_back(Input::RIGHT),
+                Key::Character(b' ') => game.pause(),
+                Key::Character(b'u') => {
+                    game.undo();
+                    replay = Replay::new(game.clone());
+                },
+                Key::Character(b'r') => {
+                    game.redo();
+                    replay = Replay::new(game.clone());
+                },
+                Key::Character(b's') => {
+                    status = save(&format!("tetris-{}.png", game.elapsed_ms()), &raster::game_to_png(&game, &Theme::classic()));
+                },
+                Key::Character(b'g') => {
+                    status = save("tetris-replay.gif", &raster::replay_to_gif(&replay, REPLAY_FRAMES_PER_IMAGE, &Theme::classic()));
+                },
+                Key::Character(b'q') | Key::Character(CTRL_C) => return Ok(()),
+                Key::Character(_) => {},
+            }
+        }
+        for _ in 0..timestep.advance(start.elapsed().as_secs_f64() * 1000.) {
+            if State::Running != game.state() {
+                break;
+            }
+            let frame_input = next_frame_input(&mut presses);
+            input::apply(&mut game, frame_input);
+            game.tick();
+            replay.record(frame_input);
+        }
+        if State::Running != game.state() {
+            presses.clear();
+        }
+        stdout.write_all(render(&game, &mut board, &mut next, &status).as_bytes())?;
+        stdout.flush()?;
+        if let State::Over | State::Finished = game.state() {
+            // Leaves the last frame on screen until a key is pressed.
+            while 0 == stdin.read(&mut input)? {
+                thread::sleep(POLL_INTERVAL);
+            }
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
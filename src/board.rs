@@ -1,6 +1,33 @@
 use super::piece::Piece;
 use super::piece;
 
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+
+const EMPTY_CELL: char = '.';
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    TooManyRows { rows: usize, max_rows: usize },
+    WrongWidth { row: usize, width: usize },
+    UnknownCell { row: usize, column: usize, character: char },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::TooManyRows {rows, max_rows} =>
+                write!(formatter, "{} rows do not fit in {}", rows, max_rows),
+            ParseError::WrongWidth {row, width} =>
+                write!(formatter, "line {} is {} cells wide", row + 1, width),
+            ParseError::UnknownCell {row, column, character} =>
+                write!(formatter, "unknown cell '{}' at line {}, column {}", character, row + 1, column + 1),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     width: usize,
     height: usize,
@@ -19,6 +46,47 @@ impl Board {
         }
     }
 
+    // One line per row, top first: `.` for an empty cell, the piece letter
+    // otherwise and `G` for garbage. Blank lines and surrounding spaces are
+    // ignored and the missing rows on top are empty.
+    pub fn from_text(text: &str, height: usize, width: usize) -> Result<Self, ParseError> {
+        let mut board = Board::new(height, width);
+        let lines: Vec<&str> = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+        if lines.len() > board.cells.len() {
+            return Err(ParseError::TooManyRows {rows: lines.len(), max_rows: board.cells.len()});
+        }
+        for (line_index, line) in lines.iter().enumerate() {
+            let cells = line.chars().enumerate()
+                .map(|(column, character)| match character {
+                    EMPTY_CELL => Ok(piece::Cell(None)),
+                    _ => piece::Type::from_letter(character)
+                        .map(|piece_type| piece::Cell(Some(piece_type)))
+                        .ok_or(ParseError::UnknownCell {row: line_index, column, character}),
+                })
+                .collect::<Result<Vec<piece::Cell>, ParseError>>()?;
+            if cells.len() != width {
+                return Err(ParseError::WrongWidth {row: line_index, width: cells.len()});
+            }
+            board.cells[lines.len() - 1 - line_index] = cells;
+        }
+        Ok(board)
+    }
+
+    // The visible rows in the `from_text` format, along with the rows above
+    // them when something sticks out.
+    pub fn to_text(&self) -> String {
+        let rows = self.cells.iter()
+            .rposition(|row| row.iter().any(|cell| cell.0.is_some()))
+            .map_or(0, |top| top + 1)
+            .max(self.height);
+        let mut text = String::with_capacity(rows * (self.width + 1));
+        for row in self.cells[..rows].iter().rev() {
+            text.extend(row.iter().map(|cell| cell.0.map_or(EMPTY_CELL, piece::Type::letter)));
+            text.push('\n');
+        }
+        text
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -35,6 +103,21 @@ impl Board {
         &self.cells
     }
 
+    // Walls and floor count as occupied, the space above the board does not.
+    pub fn is_occupied(&self, row: i32, column: i32) -> bool {
+        if row < 0 || column < 0 || column as usize >= self.width {
+            return true;
+        }
+        match self.cells.get(row as usize) {
+            None => false,
+            Some(cells) => cells[column as usize].0.is_some(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(|row| row.iter().all(|cell| cell.0.is_none()))
+    }
+
     pub fn freeze(&mut self, piece: Piece) {
         let shape = piece.shape();
         for (shape_row_index, row) in shape.iter().enumerate() {
@@ -59,6 +142,16 @@ impl Board {
         self.in_available_cells_below(piece, self.height as usize)
     }
 
+    // Where `piece` lands when dropped straight down.
+    pub fn ghost(&self, piece: &Piece) -> Piece {
+        let mut ghost = piece.clone();
+        while !self.is_colliding(&ghost) {
+            ghost.move_down();
+        }
+        ghost.revert_move_down();
+        ghost
+    }
+
     pub fn clear_lines(&mut self) -> Vec<usize> {
         let mut index_to_remove = Vec::with_capacity(self.max_piece_size as usize);
         for (row_index, row) in self.cells().iter().enumerate() {
@@ -73,11 +166,41 @@ impl Board {
                 index_to_remove.push(row_index);
             }
         }
-        for &index in index_to_remove.iter().rev() {
+        self.remove_rows(&index_to_remove);
+        index_to_remove
+    }
+
+    pub fn clear(&mut self) {
+        let all_rows: Vec<usize> = (0..self.cells.len()).collect();
+        self.remove_rows(&all_rows);
+    }
+
+    // Rows above the removed ones fall down, empty rows are added on top.
+    fn remove_rows(&mut self, ordered_indexes: &[usize]) {
+        for &index in ordered_indexes.iter().rev() {
             self.cells.remove(index);
         }
         self.cells.resize(self.height_with_hidden_top(), vec!(piece::Cell(Option::None); self.width as usize));
-        index_to_remove
+    }
+
+    // Pushes the stack up, one row per hole column, the first one ending at
+    // the bottom. Returns false when the stack no longer fits below the
+    // visible top.
+    pub fn push_garbage_rows(&mut self, hole_columns: &[usize]) -> bool {
+        for &hole_column in hole_columns {
+            let mut row = vec!(piece::Cell(Some(piece::Type::Garbage)); self.width);
+            row[hole_column] = piece::Cell(None);
+            self.cells.insert(0, row);
+        }
+        let overflow: Vec<Vec<piece::Cell>> = self.cells.drain(self.height_with_hidden_top()..).collect();
+        let is_empty = |row: &Vec<piece::Cell>| row.iter().all(|cell| cell.0.is_none());
+        overflow.iter().all(is_empty) && self.cells[self.height..].iter().all(is_empty)
+    }
+
+    pub fn garbage_row_count(&self) -> usize {
+        self.cells.iter()
+            .filter(|row| row.iter().any(|cell| matches!(cell.0, Some(piece::Type::Garbage))))
+            .count()
     }
 
     fn height_with_hidden_top(&self) -> usize {
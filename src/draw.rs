@@ -1,136 +1,412 @@
 use super::board::Board;
 use super::game::Game;
+use super::game::State;
+use super::garbage::GarbageQueue;
 use super::piece;
 use super::piece::Piece;
+use super::theme;
+use super::theme::CellStyle;
+use super::theme::GhostStyle;
+use super::theme::Theme;
 
 use std::convert::TryInto;
 use web_sys::CanvasRenderingContext2d;
-use wasm_bindgen::JsValue;
 
-struct DrawContext<'a> {
-    canvas_context: &'a CanvasRenderingContext2d,
-    zoom: usize,
+// Pixels a cell is wide on the board of the web page, and in images of
+// boards.
+pub const ZOOM: usize = 30;
+
+// Where frames are drawn, in cells: rows count from the bottom of the
+// frame and columns from its left.
+pub trait Renderer {
+    // Starts a new frame, `rows` high and `columns` wide.
+    fn clear(&mut self, rows: usize, columns: usize);
+
+    fn cell(&mut self, row: usize, column: usize, color: &str);
+
+    // Lines around all the cells of the frame.
+    fn grid(&mut self, color: &str);
+
+    // Line across the frame, below row `row`.
+    fn horizontal_line(&mut self, row: usize, color: &str);
+
+    // Text centered on row `row` of the frame.
+    fn text(&mut self, row: usize, text: &str, color: &str);
+
+    // Colors and styles frames are drawn with.
+    fn theme(&self) -> &Theme {
+        theme::classic()
+    }
+
+    // Fills the whole frame, behind everything else.
+    fn background(&mut self, _color: &str) {}
+
+    // Renderers not knowing how to draw a style draw flat cells.
+    fn styled_cell(&mut self, row: usize, column: usize, color: &str, _style: CellStyle) {
+        self.cell(row, column, color);
+    }
+
+    // Character centered in a cell, over what the cell was filled with.
+    fn glyph(&mut self, _row: usize, _column: usize, _glyph: char, _color: &str) {}
+
+    // Ends the frame started by `clear`, for renderers drawing it only then.
+    fn end_frame(&mut self) {}
+
+    fn piece(&mut self, piece: &Piece, row_offset: i32, column_offset: i32) {
+        let theme = self.theme();
+        let color = theme.pieces.get(piece.piece_type()).to_string();
+        let style = theme.cell_style;
+        let glyph = if theme.glyphs { theme::glyph(piece.piece_type()) } else { None };
+        let glyph_color = theme.glyph_color.clone();
+        for (row, column) in piece_cells(piece, row_offset, column_offset) {
+            self.styled_cell(row, column, &color, style);
+            if let Some(glyph) = glyph {
+                self.glyph(row, column, glyph, &glyph_color);
+            }
+        }
+    }
+}
+
+// The cells of a piece, those falling out of the frame are left out.
+fn piece_cells(piece: &Piece, row_offset: i32, column_offset: i32) -> Vec<(usize, usize)> {
+    let mut cells = Vec::with_capacity(4);
+    for (shape_row_index, row) in piece.shape().iter().enumerate() {
+        for (shape_column_index, cell) in row.iter().enumerate() {
+            if let None = cell.0 {
+                continue;
+            };
+
+            let row = piece::index(shape_row_index, piece.row_offset() + row_offset);
+            let column = piece::index(shape_column_index, piece.column_offset() + column_offset);
+            if let (Some(row), Some(column)) = (row, column) {
+                cells.push((row, column));
+            }
+        }
+    }
+    cells
 }
 
-impl<'a> DrawContext<'a> {
-    pub fn new(canvas_context: &'a CanvasRenderingContext2d, zoom: usize) -> DrawContext {
-        DrawContext {canvas_context, zoom}
+fn draw_ghost(ghost: &Piece, renderer: &mut impl Renderer) {
+    let theme = renderer.theme();
+    let (color, style) = match theme.ghost_style {
+        GhostStyle::Hidden => return,
+        GhostStyle::Solid => (theme.ghost.clone(), CellStyle::Flat),
+        GhostStyle::Outline => (theme.ghost.clone(), CellStyle::Outlined),
+        GhostStyle::PieceOutline => (theme.pieces.get(ghost.piece_type()).to_string(), CellStyle::Outlined),
+    };
+    for (row, column) in piece_cells(ghost, 0, 0) {
+        renderer.styled_cell(row, column, &color, style);
     }
 }
 
-pub fn draw_board(game: &Game, context: &CanvasRenderingContext2d, zoom: usize) {
+pub fn draw_board(game: &Game, renderer: &mut impl Renderer) {
+    draw_game_board(game, None, renderer);
+}
+
+// The board with where the falling piece would land, for frontends showing
+// it.
+pub fn draw_board_with_ghost(game: &Game, renderer: &mut impl Renderer) {
+    draw_game_board(game, game.ghost_piece().as_ref(), renderer);
+}
+
+fn draw_game_board(game: &Game, ghost: Option<&Piece>, renderer: &mut impl Renderer) {
     let board = game.board();
-    let canvas = context.canvas().unwrap();
-    canvas.set_width((board.width() * (zoom+1)).try_into().unwrap());
-    canvas.set_height(((board.height() + board.max_piece_size()) * (zoom+1)).try_into().unwrap());
-    context.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
-    let context = DrawContext::new(&context, zoom);
-    draw_grid(&context, board.width(), board.height() + board.max_piece_size());
-    draw_game_over_line(&context, board.width(), board.max_piece_size());
-    draw_board_cells(&context, board);
-    if let Some(piece) = game.piece() {
-        draw_piece(&context, &piece, 0, 0);
+    match game.line_clear() {
+        Some(line_clear) => {
+            paint_position(line_clear.board(), None, None, renderer);
+            let elapsed_frames = game.delays().line_are.saturating_sub(game.entry_frames());
+            draw_line_clear_flash(line_clear.rows(), elapsed_frames, board.width(), renderer);
+        },
+        None => paint_position(board, game.piece(), ghost, renderer),
     }
+    let message = match game.state() {
+        State::Paused => Some("Paused"),
+        State::Over => Some("Game over"),
+        State::Finished => Some("Finished"),
+        State::Ready | State::Running => None,
+    };
+    if let Some(message) = message {
+        let color = renderer.theme().text.clone();
+        renderer.text(board.height() / 2, message, &color);
+    }
+    renderer.end_frame();
 }
 
-pub fn draw_next_pieces(game: &Game, context: &CanvasRenderingContext2d, zoom: usize) {
-    let board = game.board();
-    let next_pieces = game.next_pieces();
-    let pieces_to_display = next_pieces.len();
-    let canvas = context.canvas().unwrap();
-    canvas.set_width((pieces_to_display * board.max_piece_size() * (zoom+1)).try_into().unwrap());
-    canvas.set_height((board.max_piece_size() * (zoom+1)).try_into().unwrap());
-    context.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
-    let context = DrawContext::new(&context, zoom);
-    draw_grid(&context, pieces_to_display * board.max_piece_size(), board.max_piece_size());
+// Frames each flash of the cleared rows lasts, and the pause between two.
+const LINE_CLEAR_FLASH_FRAMES: u32 = 4;
 
-    for (i, next_piece) in next_pieces.iter().enumerate() {
-        draw_piece(&context, &next_piece, 0, (i * board.max_piece_size()) as i32);
+// Cleared rows blink until the stack collapses, starting lit.
+fn draw_line_clear_flash(rows: &[usize], elapsed_frames: u32, width: usize, renderer: &mut impl Renderer) {
+    if 1 == elapsed_frames / LINE_CLEAR_FLASH_FRAMES % 2 {
+        return;
+    }
+    let color = renderer.theme().line_clear.clone();
+    for &row in rows {
+        for column in 0..width {
+            renderer.cell(row, column, &color);
+        }
     }
 }
 
-fn draw_grid(context: &DrawContext, width: usize, height: usize) {
-    let (context, zoom) = (context.canvas_context, context.zoom);
-    context.set_stroke_style(&JsValue::from_str("#AAA"));
-    context.begin_path();
-    for row in 0..(height+1+4) {
-        context.move_to(0., (row*(zoom+1)) as f64);
-        context.line_to((width*(zoom+1)) as f64, (row*(zoom+1)) as f64);
+// A board and what is falling on it, the ghost being drawn below the piece.
+pub fn draw_position(board: &Board, piece: Option<&Piece>, ghost: Option<&Piece>, renderer: &mut impl Renderer) {
+    paint_position(board, piece, ghost, renderer);
+    renderer.end_frame();
+}
+
+// Draws `board` with `piece` falling on it, and where it would land if
+// `ghost` is set.
+pub fn draw_position_with_ghost(board: &Board, piece: Option<&Piece>, ghost: bool, renderer: &mut impl Renderer) {
+    let ghost = match piece {
+        Some(piece) if ghost => Some(board.ghost(piece)),
+        _ => None,
+    };
+    draw_position(board, piece, ghost.as_ref(), renderer);
+}
+
+// Starts a frame with the position, for more to be drawn over it.
+fn paint_position(board: &Board, piece: Option<&Piece>, ghost: Option<&Piece>, renderer: &mut impl Renderer) {
+    let theme = renderer.theme().clone();
+    renderer.clear(board.height() + board.max_piece_size(), board.width());
+    renderer.background(&theme.background);
+    renderer.grid(&theme.grid);
+    renderer.horizontal_line(board.height(), &theme.limit);
+    draw_board_cells(renderer, board, &theme);
+    if let Some(ghost) = ghost {
+        draw_ghost(ghost, renderer);
     }
-    for column in 0..width+1 {
-        context.move_to((column*(zoom+1)) as f64, 0.);
-        context.line_to((column*(zoom+1)) as f64, ((height+4)*(zoom+1)) as f64);
+    if let Some(piece) = piece {
+        renderer.piece(piece, 0, 0);
     }
-    context.stroke();
 }
 
-fn draw_game_over_line(context: &DrawContext, width: usize, top: usize) {
-    let (context, zoom) = (context.canvas_context, context.zoom);
-    context.begin_path();
-    context.set_stroke_style(&JsValue::from_str("#F00"));
-    context.move_to(0., (top*(zoom+1)) as f64);
-    context.line_to((width*(zoom+1)) as f64, (4*(zoom+1)) as f64);
-    context.stroke();
+pub fn draw_next_pieces(game: &Game, renderer: &mut impl Renderer) {
+    let board = game.board();
+    let next_pieces = game.next_pieces();
+    let theme = renderer.theme().clone();
+    renderer.clear(board.max_piece_size(), next_pieces.len() * board.max_piece_size());
+    renderer.background(&theme.background);
+    renderer.grid(&theme.grid);
+
+    for (i, next_piece) in next_pieces.iter().enumerate() {
+        renderer.piece(next_piece, 0, (i * board.max_piece_size()) as i32);
+    }
+    renderer.end_frame();
+}
+
+// Column beside the board, one cell per incoming garbage line: ready garbage
+// at the bottom, garbage still waiting above it, in the colors of the theme.
+pub fn draw_garbage_meter(queue: &GarbageQueue, board: &Board, renderer: &mut impl Renderer) {
+    let theme = renderer.theme().clone();
+    renderer.clear(board.height() + board.max_piece_size(), 1);
+    renderer.background(&theme.background);
+    let ready_lines = queue.ready_lines() as usize;
+    let pending_lines = (queue.pending_lines() as usize).min(board.height());
+    for row in 0..pending_lines {
+        let color = if row < ready_lines { &theme.garbage_ready } else { &theme.garbage_pending };
+        renderer.cell(row, 0, color);
+    }
+    renderer.end_frame();
 }
 
-fn draw_board_cells(context: &DrawContext, board: &Board) {
+fn draw_board_cells(renderer: &mut impl Renderer, board: &Board, theme: &Theme) {
     for (row_index, row) in board.cells().iter().enumerate() {
         for (column_index, cell) in row.iter().enumerate() {
-            let color = get_cell_color(cell);
-            draw_cell_with_color(context, row_index, column_index, &color);
+            let style = if cell.0.is_some() { theme.cell_style } else { CellStyle::Flat };
+            renderer.styled_cell(row_index, column_index, theme.cell_color(cell), style);
+            let glyph = if theme.glyphs { cell.0.and_then(theme::glyph) } else { None };
+            if let Some(glyph) = glyph {
+                renderer.glyph(row_index, column_index, glyph, &theme.glyph_color);
+            }
         }
     }
 }
 
-fn draw_piece(context: &DrawContext, piece: &Piece, row_offset: i32, column_offset: i32) {
-    let mut is_color_set = false;
-    for (shape_row_index, row) in piece.shape().iter().enumerate() {
-        for (shape_column_index, cell) in row.iter().enumerate() {
-            if let None = cell.0 {
-                continue;
-            };
+// Pixel lines of a frame `rows` high, with a one pixel line between cells
+// `zoom` pixels wide: the line below `row` and the line left of `column`.
+pub fn line_y(rows: usize, row: usize, zoom: usize) -> usize {
+    (rows - row) * (zoom + 1)
+}
 
-            let row = piece::index(shape_row_index, piece.row_offset() + row_offset);
-            let column = piece::index(shape_column_index, piece.column_offset() + column_offset);
-            let (row, column) = match (row, column) {
-                (Some(row), Some(column)) => (row, column),
-                _ => continue,
-            };
-            if !is_color_set {
-                let color = get_cell_color(cell);
-                context.canvas_context.set_fill_style(&JsValue::from_str(&color));
-                is_color_set = true;
-            }
-            draw_cell(context, row, column);
+pub fn line_x(column: usize, zoom: usize) -> usize {
+    column * (zoom + 1)
+}
+
+// Width of the edges of beveled and outlined cells `zoom` pixels wide.
+pub fn edge_width(zoom: usize) -> usize {
+    (zoom / 8).max(1)
+}
+
+// Draws on a canvas, each cell `zoom` pixels of the canvas wide with a one
+// pixel line between cells.
+pub struct CanvasRenderer {
+    context: CanvasRenderingContext2d,
+    zoom: usize,
+    // Pixels of the canvas per CSS pixel, so that the canvas shows at the
+    // same size on every screen and each of its pixels on its own one.
+    pixel_ratio: f64,
+    rows: usize,
+    columns: usize,
+    // Of the canvas, only set again when it changes as that reallocates it.
+    size: (u32, u32),
+    theme: Theme,
+}
+
+impl CanvasRenderer {
+    pub fn new(context: CanvasRenderingContext2d, zoom: usize) -> Self {
+        CanvasRenderer {context, zoom, pixel_ratio: 1., rows: 0, columns: 0, size: (0, 0), theme: Theme::classic()}
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn zoom(&self) -> usize {
+        self.zoom
+    }
+
+    // Takes effect from the next frame.
+    pub fn set_zoom(&mut self, zoom: usize) {
+        self.zoom = zoom.max(1);
+    }
+
+    pub fn set_pixel_ratio(&mut self, pixel_ratio: f64) {
+        if pixel_ratio != self.pixel_ratio {
+            self.pixel_ratio = pixel_ratio;
+            // For the CSS size to be set again.
+            self.size = (0, 0);
         }
     }
-}
 
-fn draw_cell_with_color(context: &DrawContext, row: usize, column: usize, color: &str) {
-    context.canvas_context.set_fill_style(&JsValue::from_str(color));
-    draw_cell(context, row, column);
+    fn y(&self, row: usize) -> f64 {
+        line_y(self.rows, row, self.zoom) as f64
+    }
+
+    fn x(&self, column: usize) -> f64 {
+        line_x(column, self.zoom) as f64
+    }
+
+    // Sizes the canvas for the frame, its CSS size following the pixel ratio.
+    fn resize(&mut self, width: u32, height: u32) {
+        let canvas = self.context.canvas().unwrap();
+        canvas.set_width(width);
+        canvas.set_height(height);
+        let style = canvas.style();
+        let _ = style.set_property("width", &format!("{}px", width as f64 / self.pixel_ratio));
+        let _ = style.set_property("height", &format!("{}px", height as f64 / self.pixel_ratio));
+        self.size = (width, height);
+    }
 }
 
-fn draw_cell(context: &DrawContext, row: usize, column: usize) {
-    let DrawContext{canvas_context: context, zoom} = *context;
-    context.fill_rect(
-        (column * (zoom as usize + 1) + 1) as f64,
-        (context.canvas().unwrap().height() as usize - (row + 1) * (zoom as usize + 1) + 1) as f64,
-        zoom as f64,
-        zoom as f64
-    );
-    context.stroke();
+// Largest zoom drawing `rows` by `columns` cells and the lines around them
+// within `width` by `height` pixels, one pixel at least.
+pub fn fit_zoom(rows: usize, columns: usize, width: f64, height: f64) -> usize {
+    let fit = |cells: usize, pixels: f64| ((pixels - 1.) / cells as f64).floor() - 1.;
+    fit(rows, height).min(fit(columns, width)).max(1.) as usize
 }
 
-fn get_cell_color(cell: &piece::Cell) -> String {
-    match cell.0 {
-        Some(piece::Type::I) => "cyan".to_string(),
-        Some(piece::Type::T) => "purple".to_string(),
-        Some(piece::Type::O) => "yellow".to_string(),
-        Some(piece::Type::L) => "orange".to_string(),
-        Some(piece::Type::J) => "blue".to_string(),
-        Some(piece::Type::S) => "lime".to_string(),
-        Some(piece::Type::Z) => "red".to_string(),
-        None => "#DDD".to_string(),
+impl Renderer for CanvasRenderer {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.rows = rows;
+        self.columns = columns;
+        // One more pixel for the lines on the right and bottom edges.
+        let width = (columns * (self.zoom + 1) + 1).try_into().unwrap();
+        let height = (rows * (self.zoom + 1) + 1).try_into().unwrap();
+        if (width, height) != self.size {
+            self.resize(width, height);
+        } else {
+            self.context.clear_rect(0.0, 0.0, width.into(), height.into());
+        }
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        self.context.set_fill_style_str(color);
+        self.context.fill_rect(self.x(column) + 1., self.y(row + 1) + 1., self.zoom as f64, self.zoom as f64);
+    }
+
+    fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    fn background(&mut self, color: &str) {
+        let canvas = self.context.canvas().unwrap();
+        self.context.set_fill_style_str(color);
+        self.context.fill_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
+    }
+
+    fn styled_cell(&mut self, row: usize, column: usize, color: &str, style: CellStyle) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let (x, y, size) = (self.x(column) + 1., self.y(row + 1) + 1., self.zoom as f64);
+        let edge = edge_width(self.zoom) as f64;
+        match style {
+            CellStyle::Flat => self.cell(row, column, color),
+            CellStyle::Beveled => {
+                self.cell(row, column, color);
+                self.context.set_fill_style_str("rgba(255, 255, 255, 0.45)");
+                self.context.fill_rect(x, y, size, edge);
+                self.context.fill_rect(x, y + edge, edge, size - edge);
+                self.context.set_fill_style_str("rgba(0, 0, 0, 0.35)");
+                self.context.fill_rect(x + edge, y + size - edge, size - edge, edge);
+                self.context.fill_rect(x + size - edge, y + edge, edge, size - 2. * edge);
+            },
+            CellStyle::Outlined => {
+                self.context.set_stroke_style_str(color);
+                self.context.set_line_width(edge);
+                self.context.stroke_rect(x + edge / 2., y + edge / 2., size - edge, size - edge);
+                self.context.set_line_width(1.);
+            },
+        }
+    }
+
+    fn glyph(&mut self, row: usize, column: usize, glyph: char, color: &str) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        self.context.set_fill_style_str(color);
+        self.context.set_font(&format!("bold {}px sans-serif", self.zoom * 2 / 3));
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+        let half = (self.zoom + 1) as f64 / 2.;
+        let _ = self.context.fill_text(&glyph.to_string(), self.x(column) + half, self.y(row + 1) + half);
+    }
+
+    // Lines go through the middle of their pixels, to fill them and only
+    // them rather than blur over two.
+    fn grid(&mut self, color: &str) {
+        let (right, bottom) = (self.x(self.columns) + 1., self.y(0) + 1.);
+        self.context.set_stroke_style_str(color);
+        self.context.begin_path();
+        for row in 0..=self.rows {
+            self.context.move_to(0., self.y(row) + 0.5);
+            self.context.line_to(right, self.y(row) + 0.5);
+        }
+        for column in 0..=self.columns {
+            self.context.move_to(self.x(column) + 0.5, 0.);
+            self.context.line_to(self.x(column) + 0.5, bottom);
+        }
+        self.context.stroke();
+    }
+
+    fn horizontal_line(&mut self, row: usize, color: &str) {
+        self.context.set_stroke_style_str(color);
+        self.context.begin_path();
+        self.context.move_to(0., self.y(row) + 0.5);
+        self.context.line_to(self.x(self.columns) + 1., self.y(row) + 0.5);
+        self.context.stroke();
+    }
+
+    fn text(&mut self, row: usize, text: &str, color: &str) {
+        self.context.set_fill_style_str(color);
+        self.context.set_font(&format!("bold {}px sans-serif", self.zoom));
+        self.context.set_text_align("center");
+        self.context.set_text_baseline("middle");
+        let y = self.y(row) - (self.zoom + 1) as f64 / 2.;
+        let _ = self.context.fill_text(text, self.x(self.columns) / 2., y);
     }
 }
@@ -0,0 +1,516 @@
+use super::board::Board;
+use super::piece::Orientation;
+use super::piece::Piece;
+use super::piece::Type;
+
+use std::convert::TryInto;
+use std::fmt;
+
+// Fumen (v115) is the format the community shares setups in: pages of a
+// 10 wide field, each with an optional piece and comment, packed in a
+// base 64 string.
+
+const PREFIX: &str = "v115@";
+const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const COMMENT_CHARACTERS: &[u8] = b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const COMMENT_BASE: u32 = 96;
+const MAX_COMMENT_LENGTH: usize = 4095;
+
+const WIDTH: usize = 10;
+const HEIGHT: usize = 23;
+// The field rows plus the garbage row below them, which is rised into the
+// field by the pages asking so.
+const CELLS: usize = WIDTH * (HEIGHT + 1);
+const UNCHANGED: u32 = 8;
+
+// Board height of the decoded pages, the one of the game.
+const BOARD_HEIGHT: usize = 20;
+
+pub struct Page {
+    pub board: Board,
+    pub piece: Option<Piece>,
+    pub comment: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    MissingPrefix,
+    InvalidCharacter(char),
+    UnexpectedEnd,
+    InvalidField,
+    InvalidPiece,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::MissingPrefix => write!(formatter, "not a {} fumen", PREFIX),
+            DecodeError::InvalidCharacter(character) => write!(formatter, "unexpected character '{}'", character),
+            DecodeError::UnexpectedEnd => write!(formatter, "fumen ends too early"),
+            DecodeError::InvalidField => write!(formatter, "field changes overflow the field"),
+            DecodeError::InvalidPiece => write!(formatter, "piece out of the field"),
+        }
+    }
+}
+
+// Cells from the garbage row up, as fumen block numbers.
+type Field = [u8; CELLS];
+
+fn block(piece_type: Option<Type>) -> u8 {
+    match piece_type {
+        None => 0,
+        Some(Type::I) => 1,
+        Some(Type::L) => 2,
+        Some(Type::O) => 3,
+        Some(Type::Z) => 4,
+        Some(Type::T) => 5,
+        Some(Type::J) => 6,
+        Some(Type::S) => 7,
+        Some(Type::Garbage) => 8,
+    }
+}
+
+fn block_type(block: u8) -> Option<Type> {
+    match block {
+        1 => Some(Type::I),
+        2 => Some(Type::L),
+        3 => Some(Type::O),
+        4 => Some(Type::Z),
+        5 => Some(Type::T),
+        6 => Some(Type::J),
+        7 => Some(Type::S),
+        8 => Some(Type::Garbage),
+        _ => None,
+    }
+}
+
+// Index in the field of a cell, row -1 being the garbage row.
+fn field_index(row: i32, column: i32) -> Option<usize> {
+    if row < -1 || row >= HEIGHT as i32 || column < 0 || column >= WIDTH as i32 {
+        return None;
+    }
+    Some((row + 1) as usize * WIDTH + column as usize)
+}
+
+// Fumen lists the cells from the top left, the garbage row last.
+fn fumen_order() -> impl Iterator<Item = usize> {
+    (0..HEIGHT + 1).rev().flat_map(|row| (0..WIDTH).map(move |column| row * WIDTH + column))
+}
+
+// Fumen rotations are counted from the upside down piece, clockwise. The
+// game's clockwise rotation goes the other way.
+fn rotation(orientation: Orientation) -> u32 {
+    match orientation {
+        Orientation::Bottom => 0,
+        Orientation::Left => 1,
+        Orientation::Top => 2,
+        Orientation::Right => 3,
+    }
+}
+
+// Cells of a spawned piece around its center, as (column, row) with rows
+// going up, then turned clockwise `rotation` times past the upside down
+// position.
+fn piece_cells(piece_type: Type, rotation: u32) -> [(i32, i32); 4] {
+    let cells = match piece_type {
+        Type::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        Type::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        Type::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+        Type::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+        Type::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+        Type::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+        Type::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        Type::Garbage => panic!("garbage has no shape"),
+    };
+    cells.map(|(column, row)| match rotation {
+        0 => (-column, -row),
+        1 => (row, -column),
+        2 => (column, row),
+        _ => (-row, column),
+    })
+}
+
+// Fumen places some pieces from another cell than their center, the
+// offset going from that cell to the center.
+fn center_offset(piece_type: Type, rotation: u32) -> (i32, i32) {
+    match (piece_type, rotation) {
+        (Type::O, 3) => (1, -1),
+        (Type::O, 0) => (1, 0),
+        (Type::O, 2) => (0, -1),
+        (Type::I, 0) => (1, 0),
+        (Type::I, 3) => (0, -1),
+        (Type::S, 2) => (0, -1),
+        (Type::S, 1) => (-1, 0),
+        (Type::Z, 2) => (0, -1),
+        (Type::Z, 3) => (1, 0),
+        _ => (0, 0),
+    }
+}
+
+fn bottom_left(cells: &[(i32, i32)]) -> (i32, i32) {
+    (cells.iter().map(|cell| cell.0).min().unwrap(), cells.iter().map(|cell| cell.1).min().unwrap())
+}
+
+// Cells of a piece on the board, as (column, row).
+fn cells_on_board(piece: &Piece) -> Vec<(i32, i32)> {
+    let mut cells = Vec::with_capacity(4);
+    for (shape_row_index, row) in piece.shape().iter().enumerate() {
+        for (shape_column_index, cell) in row.iter().enumerate() {
+            if cell.0.is_some() {
+                cells.push((
+                    shape_column_index as i32 + piece.column_offset(),
+                    shape_row_index as i32 + piece.row_offset(),
+                ));
+            }
+        }
+    }
+    cells
+}
+
+fn rotated_piece(row_offset: i32, column_offset: i32, piece_type: Type, rotation: u32) -> Piece {
+    let mut piece = Piece::new(row_offset, column_offset, piece_type);
+    while rotation != self::rotation(piece.orientation()) {
+        piece.rotate_clockwise();
+    }
+    piece
+}
+
+fn piece_from_cells(piece_type: Type, rotation: u32, cells: &[(i32, i32)]) -> Piece {
+    let (column, row) = bottom_left(cells);
+    let (shape_column, shape_row) = bottom_left(&cells_on_board(&rotated_piece(0, 0, piece_type, rotation)));
+    rotated_piece(row - shape_row, column - shape_column, piece_type, rotation)
+}
+
+struct Writer {
+    digits: Vec<u8>,
+}
+
+impl Writer {
+    fn push(&mut self, mut value: u32, digits: usize) {
+        for _ in 0..digits {
+            self.digits.push(DIGITS[(value % 64) as usize]);
+            value /= 64;
+        }
+    }
+}
+
+struct Reader {
+    values: Vec<u32>,
+    position: usize,
+}
+
+impl Reader {
+    fn poll(&mut self, digits: usize) -> Result<u32, DecodeError> {
+        let mut value = 0;
+        for digit in 0..digits {
+            let digit_value = *self.values.get(self.position).ok_or(DecodeError::UnexpectedEnd)?;
+            value += digit_value << (6 * digit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn is_done(&self) -> bool {
+        self.position >= self.values.len()
+    }
+}
+
+pub fn encode(pages: &[Page]) -> String {
+    let mut writer = Writer {digits: Vec::new()};
+    let mut previous_field: Field = [0; CELLS];
+    let mut previous_comment = "";
+    // Where the count of pages repeating the last field is, while there
+    // is room left in it.
+    let mut repeat_index: Option<usize> = None;
+    for (page_index, page) in pages.iter().enumerate() {
+        let field = field_from_board(&page.board);
+        let diffs: Vec<u32> = fumen_order()
+            .map(|index| field[index] as u32 + UNCHANGED - previous_field[index] as u32)
+            .collect();
+        if diffs.iter().all(|&diff| UNCHANGED == diff) {
+            match repeat_index {
+                Some(index) if DIGITS[63] != writer.digits[index] => {
+                    let count = DIGITS.iter().position(|&digit| digit == writer.digits[index]).unwrap();
+                    writer.digits[index] = DIGITS[count + 1];
+                },
+                _ => {
+                    writer.push(UNCHANGED * CELLS as u32 + CELLS as u32 - 1, 2);
+                    repeat_index = Some(writer.digits.len());
+                    writer.push(0, 1);
+                },
+            }
+        } else {
+            repeat_index = None;
+            for run in diffs.chunk_by(|a, b| a == b) {
+                writer.push(run[0] * CELLS as u32 + run.len() as u32 - 1, 2);
+            }
+        }
+
+        let piece = page.piece.as_ref().and_then(encode_piece);
+        let (piece_block, piece_rotation, coordinate) = piece.map_or((0, 0, 0), |(piece_type, rotation, coordinate)| {
+            (block(Some(piece_type)) as u32, rotation, coordinate)
+        });
+        let is_comment = page.comment != previous_comment;
+        let is_color = 0 == page_index;
+        // The flags, from the highest one: the piece does not lock, the
+        // comment changes, guideline colors, mirror and rise.
+        let mut action = 0;
+        action = action * 2 + is_comment as u32;
+        action = action * 2 + is_color as u32;
+        action *= 4;
+        action = (action * CELLS as u32 + coordinate) * 4 + piece_rotation;
+        writer.push(action * 8 + piece_block, 3);
+        if is_comment {
+            encode_comment(&mut writer, &page.comment);
+            previous_comment = &page.comment;
+        }
+
+        previous_field = field;
+        match (&page.piece, piece) {
+            (Some(piece), Some(_)) => lock(&mut previous_field, &cells_on_board(piece), block(Some(piece.piece_type()))),
+            _ => lock(&mut previous_field, &[], 0),
+        }
+    }
+
+    let data = String::from_utf8(writer.digits).unwrap();
+    let mut fumen = String::from(PREFIX);
+    // Long fumens are cut with question marks, the first line being shorter
+    // by the prefix.
+    let (head, mut tail) = data.split_at(data.len().min(42));
+    fumen.push_str(head);
+    while !tail.is_empty() {
+        let (line, rest) = tail.split_at(tail.len().min(47));
+        fumen.push('?');
+        fumen.push_str(line);
+        tail = rest;
+    }
+    fumen
+}
+
+// Accepts a fumen alone or anywhere in a text, e.g. an URL.
+pub fn decode(text: &str) -> Result<Vec<Page>, DecodeError> {
+    let start = text.find(PREFIX).ok_or(DecodeError::MissingPrefix)? + PREFIX.len();
+    let data = text[start..].split(|character: char| character.is_whitespace() || '&' == character || '#' == character)
+        .next().unwrap();
+    let values = data.chars()
+        .filter(|&character| '?' != character)
+        .map(|character| DIGITS.iter().position(|&digit| digit as char == character)
+            .map(|value| value as u32)
+            .ok_or(DecodeError::InvalidCharacter(character)))
+        .collect::<Result<Vec<u32>, DecodeError>>()?;
+    let mut reader = Reader {values, position: 0};
+
+    let mut pages = Vec::new();
+    let mut previous_field: Field = [0; CELLS];
+    let mut comment = String::new();
+    let mut repeat_count = 0;
+    while !reader.is_done() {
+        let mut field = previous_field;
+        if repeat_count > 0 {
+            repeat_count -= 1;
+        } else {
+            let mut cells = fumen_order();
+            let mut is_changed = true;
+            let mut decoded = 0;
+            while decoded < CELLS {
+                let value = reader.poll(2)?;
+                let (diff, count) = (value / CELLS as u32, value % CELLS as u32 + 1);
+                if UNCHANGED == diff && CELLS as u32 == count {
+                    is_changed = false;
+                }
+                for _ in 0..count {
+                    let index = cells.next().ok_or(DecodeError::InvalidField)?;
+                    field[index] = (field[index] as u32 + diff).checked_sub(UNCHANGED)
+                        .filter(|&block| block <= 8)
+                        .ok_or(DecodeError::InvalidField)? as u8;
+                }
+                decoded += count as usize;
+            }
+            if !is_changed {
+                repeat_count = reader.poll(1)?;
+            }
+        }
+
+        let mut action = reader.poll(3)?;
+        let piece_block = (action % 8) as u8;
+        action /= 8;
+        let rotation = action % 4;
+        action /= 4;
+        let coordinate = action % CELLS as u32;
+        action /= CELLS as u32;
+        let is_rise = 1 == action % 2;
+        let is_mirror = 1 == (action >> 1) % 2;
+        let is_comment = 1 == (action >> 3) % 2;
+        let is_lock = 0 == (action >> 4) % 2;
+        if is_comment {
+            comment = decode_comment(&mut reader)?;
+        }
+
+        let piece_cells = match block_type(piece_block) {
+            None | Some(Type::Garbage) => None,
+            Some(piece_type) => {
+                let cells = decode_piece(piece_type, rotation, coordinate);
+                if cells.iter().any(|&(column, row)| row < 0 || field_index(row, column).is_none()) {
+                    return Err(DecodeError::InvalidPiece);
+                }
+                Some((piece_type, cells))
+            },
+        };
+        pages.push(Page {
+            board: board_from_field(&field),
+            piece: piece_cells.as_ref().map(|(piece_type, cells)| piece_from_cells(*piece_type, rotation, cells)),
+            comment: comment.clone(),
+        });
+
+        if is_lock {
+            let (piece_type, cells) = piece_cells.map_or((None, Vec::new()), |(piece_type, cells)| (Some(piece_type), cells));
+            lock(&mut field, &cells, block(piece_type));
+            if is_rise {
+                field.copy_within(0..CELLS - WIDTH, WIDTH);
+                field[..WIDTH].fill(0);
+            }
+            if is_mirror {
+                for row in field[WIDTH..].chunks_mut(WIDTH) {
+                    row.reverse();
+                }
+            }
+        }
+        previous_field = field;
+    }
+    Ok(pages)
+}
+
+fn field_from_board(board: &Board) -> Field {
+    let mut field = [0; CELLS];
+    for (row, cells) in board.cells().iter().take(HEIGHT).enumerate() {
+        for (column, cell) in cells.iter().take(WIDTH).enumerate() {
+            field[field_index(row as i32, column as i32).unwrap()] = block(cell.0);
+        }
+    }
+    field
+}
+
+fn board_from_field(field: &Field) -> Board {
+    let text: String = field[WIDTH..].chunks(WIDTH).rev()
+        .flat_map(|row| row.iter()
+            .map(|&block| block_type(block).map_or('.', Type::letter))
+            .chain(std::iter::once('\n')))
+        .collect();
+    Board::from_text(&text, BOARD_HEIGHT, WIDTH).unwrap()
+}
+
+// Puts the cells in the field then clears the full rows, leaving the
+// garbage row alone.
+fn lock(field: &mut Field, cells: &[(i32, i32)], block: u8) {
+    for &(column, row) in cells {
+        if let Some(index) = field_index(row, column) {
+            field[index] = block;
+        }
+    }
+    let rows: Vec<[u8; WIDTH]> = field[WIDTH..].chunks(WIDTH)
+        .filter(|row| row.contains(&0))
+        .map(|row| row.try_into().unwrap())
+        .collect();
+    field[WIDTH..].fill(0);
+    for (row, cells) in rows.iter().enumerate() {
+        field[(row + 1) * WIDTH..(row + 2) * WIDTH].copy_from_slice(cells);
+    }
+}
+
+// Returns the piece type, rotation and coordinate of a piece fully in the
+// field.
+fn encode_piece(piece: &Piece) -> Option<(Type, u32, u32)> {
+    let piece_type = piece.piece_type();
+    let rotation = rotation(piece.orientation());
+    let cells = cells_on_board(piece);
+    let (column, row) = bottom_left(&cells);
+    let (shape_column, shape_row) = bottom_left(&piece_cells(piece_type, rotation));
+    let (offset_column, offset_row) = center_offset(piece_type, rotation);
+    let column = column - shape_column - offset_column;
+    let row = row - shape_row - offset_row;
+    if cells.iter().any(|&(column, row)| row < 0 || field_index(row, column).is_none()) {
+        return None;
+    }
+    Some((piece_type, rotation, ((HEIGHT as i32 - 1 - row) * WIDTH as i32 + column) as u32))
+}
+
+fn decode_piece(piece_type: Type, rotation: u32, coordinate: u32) -> Vec<(i32, i32)> {
+    let (offset_column, offset_row) = center_offset(piece_type, rotation);
+    let column = (coordinate % WIDTH as u32) as i32 + offset_column;
+    let row = HEIGHT as i32 - 1 - (coordinate / WIDTH as u32) as i32 + offset_row;
+    piece_cells(piece_type, rotation).iter()
+        .map(|&(cell_column, cell_row)| (column + cell_column, row + cell_row))
+        .collect()
+}
+
+// Comments are escaped like JavaScript's `escape` then packed four
+// characters per five digits.
+fn encode_comment(writer: &mut Writer, comment: &str) {
+    let mut escaped = escape(comment);
+    escaped.truncate(MAX_COMMENT_LENGTH);
+    writer.push(escaped.len() as u32, 2);
+    for characters in escaped.as_bytes().chunks(4) {
+        let value = characters.iter().rev().fold(0, |value, &character| {
+            let index = COMMENT_CHARACTERS.iter().position(|&known| known == character).unwrap();
+            value * COMMENT_BASE + index as u32
+        });
+        writer.push(value, 5);
+    }
+}
+
+fn decode_comment(reader: &mut Reader) -> Result<String, DecodeError> {
+    let length = reader.poll(2)? as usize;
+    let mut escaped = String::with_capacity(length + 3);
+    for _ in 0..length.div_ceil(4) {
+        let mut value = reader.poll(5)?;
+        for _ in 0..4 {
+            let character = COMMENT_CHARACTERS.get((value % COMMENT_BASE) as usize).copied().unwrap_or(b' ');
+            escaped.push(character as char);
+            value /= COMMENT_BASE;
+        }
+    }
+    escaped.truncate(length);
+    Ok(unescape(&escaped))
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if character.is_ascii_alphanumeric() || "@*_+-./".contains(character) {
+            escaped.push(character);
+            continue;
+        }
+        let mut units = [0; 2];
+        for &unit in character.encode_utf16(&mut units).iter() {
+            if unit < 0x100 {
+                escaped.push_str(&format!("%{:02X}", unit));
+            } else {
+                escaped.push_str(&format!("%u{:04X}", unit));
+            }
+        }
+    }
+    escaped
+}
+
+fn unescape(escaped: &str) -> String {
+    let mut units = Vec::with_capacity(escaped.len());
+    let mut rest = escaped;
+    while let Some(character) = rest.chars().next() {
+        let (unit, length) = match (rest.strip_prefix("%u"), rest.strip_prefix('%')) {
+            (Some(hex), _) => (hex.get(..4).and_then(|hex| u16::from_str_radix(hex, 16).ok()), 6),
+            (None, Some(hex)) => (hex.get(..2).and_then(|hex| u16::from_str_radix(hex, 16).ok()), 3),
+            _ => (None, 0),
+        };
+        match unit {
+            Some(unit) => {
+                units.push(unit);
+                rest = &rest[length..];
+            },
+            None => {
+                units.push(character as u16);
+                rest = &rest[character.len_utf8()..];
+            },
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
@@ -1,118 +1,529 @@
+use super::attack::AttackTable;
+use super::attack::Clear;
+use super::attack::Guideline;
+use super::attack::TSpin;
 use super::board::Board;
+use super::game_loop;
+use super::gravity;
+use super::history::History;
+use super::mode::Mode;
+use super::mode::Report;
+use super::piece;
+use super::piece::Orientation;
 use super::piece::Piece;
 use super::piece_type_bag_generator::PieceTypeGenerator;
+use super::utils::log;
 
+use serde::Deserialize;
+use serde::Serialize;
 use std::collections::VecDeque;
-use web_sys::console;
 
+const HISTORY_CAPACITY: usize = 100;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum State {
+    Ready,
+    Running,
+    Paused,
+    Over,
+    Finished,
+}
+
+// Notable changes, collected until the owner of the game takes them. Only
+// games asked to with `record_events` collect them, so that those nobody
+// takes them from do not pile them up.
+#[derive(Clone)]
+pub enum Event {
+    // Lines the lock sends, by the attack table of the game.
+    Lock { cleared_rows: Vec<usize>, clear: Clear, attack: u32 },
+    TopOut,
+    Finish,
+}
+
+// Frames without a falling piece after each lock, ARE in guideline speak:
+// `line_are` after locks clearing lines, while the cleared rows are shown
+// before the stack collapses, and `are` after the others.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Delays {
+    pub are: u32,
+    pub line_are: u32,
+}
+
+impl Delays {
+    // Competitive modes race against the clock, pieces come right away.
+    pub const NONE: Delays = Delays {are: 0, line_are: 0};
+}
+
+impl Default for Delays {
+    fn default() -> Self {
+        Delays {are: 6, line_are: 24}
+    }
+}
+
+// Rows cleared by the last lock, shown until the next piece spawns.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LineClear {
+    board: Board,
+    rows: Vec<usize>,
+}
+
+impl LineClear {
+    // The board as it was before the rows were cleared.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+}
+
+// What is restored by undo and redo: the game as it was when a piece spawned.
+#[derive(Clone)]
+struct Snapshot {
+    board: Board,
+    piece: Option<Piece>,
+    next_pieces: VecDeque<Piece>,
+    generator: PieceTypeGenerator,
+    score: u32,
+    lines: u32,
+    pieces: u32,
+    attack: u32,
+    combo: Option<u32>,
+    back_to_back: Option<u32>,
+}
+
+// Undo history and pending events are not saved, see `save`.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Game {
+    state: State,
+    #[serde(with = "super::mode")]
+    mode: Option<Box<dyn Mode>>,
     board: Board,
     piece: Option<Piece>,
     next_pieces_capacity: usize,
     next_pieces: VecDeque<Piece>,
+    #[serde(skip)]
     _shadow_piece: Option<Piece>,
     score: u32,
+    lines: u32,
+    pieces: u32,
+    // Lines sent so far, by `attack_table`.
+    #[serde(default)]
+    attack: u32,
+    #[serde(skip, default = "guideline")]
+    attack_table: Box<dyn AttackTable>,
+    combo: Option<u32>,
+    back_to_back: Option<u32>,
+    last_move_rotation: bool,
+    frame: u64,
     generator: PieceTypeGenerator,
+    level: u32,
+    rows_per_tick: f64,
+    pending_rows: f64,
+    #[serde(default)]
+    delays: Delays,
+    // Frames left before the next piece spawns, see `Delays`.
+    #[serde(default)]
+    entry_frames: u32,
+    #[serde(default)]
+    line_clear: Option<LineClear>,
+    #[serde(skip)]
+    spawn_snapshot: Option<Snapshot>,
+    #[serde(skip, default = "new_history")]
+    history: History<Snapshot>,
+    // Undo without a mode, free play. Modes decide for themselves.
+    #[serde(default)]
+    undoable: bool,
+    #[serde(skip)]
+    records_events: bool,
+    #[serde(skip)]
+    events: Vec<Event>,
+}
+
+fn guideline() -> Box<dyn AttackTable> {
+    Box::new(Guideline)
+}
+
+fn new_history() -> History<Snapshot> {
+    History::new(HISTORY_CAPACITY)
 }
 
 impl Game {
     pub fn new() -> Self {
         let next_pieces_capacity: usize = 3;
         Game{
+            state: State::Ready,
+            mode: None,
             board: Board::new(20, 10),
             piece: None,
             next_pieces_capacity,
             next_pieces: VecDeque::with_capacity(next_pieces_capacity),
             _shadow_piece: None,
             score: 0,
+            lines: 0,
+            pieces: 0,
+            attack: 0,
+            attack_table: guideline(),
+            combo: None,
+            back_to_back: None,
+            last_move_rotation: false,
+            frame: 0,
             generator: PieceTypeGenerator::new(),
+            level: 1,
+            rows_per_tick: gravity::guideline_rows_per_tick(1),
+            pending_rows: 0.,
+            delays: Delays::default(),
+            entry_frames: 0,
+            line_clear: None,
+            spawn_snapshot: None,
+            history: new_history(),
+            undoable: false,
+            records_events: false,
+            events: Vec::new(),
         }
     }
 
+    // Games sharing a seed get the same pieces in the same order.
+    pub fn with_seed(seed: u64) -> Self {
+        let mut game = Self::new();
+        game.generator = PieceTypeGenerator::with_seed(seed);
+        game
+    }
+
+    pub fn with_mode(mode: Box<dyn Mode>) -> Self {
+        let mut game = Self::new();
+        game.delays = mode.delays();
+        game.mode = Some(mode);
+        game
+    }
+
+    // How the attack of each lock is counted, the guideline table unless
+    // set otherwise.
+    pub fn set_attack_table(&mut self, attack_table: Box<dyn AttackTable>) {
+        self.attack_table = attack_table;
+    }
+
+    // Lets a game without a mode undo and redo moves.
+    pub fn allow_undo(&mut self) {
+        self.undoable = true;
+    }
+
+    // How many moves can be undone, none with 0: the positions to go back
+    // to are then not even recorded. Forgets the ones recorded so far.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history = History::new(capacity);
+        self.record_spawn();
+    }
+
+    // Takes effect from the next lock.
+    pub fn set_delays(&mut self, delays: Delays) {
+        self.delays = delays;
+    }
+
     pub fn pause(&mut self) {
-        console::log_1(&"Pause".into());
+        log("Pause");
+        self.state = match self.state {
+            State::Running => State::Paused,
+            State::Paused => State::Running,
+            state => state,
+        };
+    }
+
+    // Advances the simulation by one fixed step, see `game_loop`.
+    pub fn tick(&mut self) {
+        if State::Running != self.state {
+            return;
+        }
+        self.frame += 1;
+        self.call_mode((), |mode, game| mode.tick(game));
+        if State::Running != self.state {
+            return;
+        }
+        if 0 < self.entry_frames {
+            self.entry_frames -= 1;
+            if 0 == self.entry_frames {
+                self.line_clear = None;
+                self.spawn();
+            }
+            return;
+        }
+        self.pending_rows += self.rows_per_tick;
+        while self.pending_rows >= 1. {
+            self.pending_rows -= 1.;
+            let pieces = self.pieces;
+            self.move_down();
+            if pieces != self.pieces || State::Running != self.state {
+                self.pending_rows = 0.;
+            }
+        }
+    }
+
+    pub fn set_level(&mut self, level: u32) {
+        log(&format!("level: {}", level));
+        self.level = level;
+        self.set_gravity(gravity::guideline_rows_per_tick(level));
+    }
+
+    // Rows fallen per tick, `0.` disables gravity altogether.
+    pub fn set_gravity(&mut self, rows_per_tick: f64) {
+        self.rows_per_tick = rows_per_tick;
+        self.pending_rows = 0.;
     }
 
     pub fn move_down(&mut self) {
-        console::log_1(&"move_down".into());
+        log("move_down");
         let piece = match self.piece.as_mut() {
             None => {
-                console::log_1(&"no piece".into());
+                log("no piece");
                 return;
             },
             Some(piece) => piece,
         };
         piece.move_down();
         if !self.board.is_colliding(&piece) {
-            console::log_1(&"no collision".into());
+            log("no collision");
+            self.last_move_rotation = false;
             return
         }
-        console::log_1(&"collision".into());
+        log("collision");
         piece.revert_move_down();
         if !self.board.is_fully_in(&piece) {
             self.game_over();
             return
         }
-        self.board.freeze(self.piece.take().unwrap());
-        console::log_1(&"froze to board".into());
+        let piece = self.piece.take().unwrap();
+        let t_spin = self.t_spin(&piece);
+        self.board.freeze(piece);
+        if let Some(snapshot) = self.spawn_snapshot.take() {
+            self.history.push(snapshot);
+        }
+        log("froze to board");
+        let board_before_clear = if 0 < self.delays.line_are { Some(self.board.clone()) } else { None };
         let cleared_lines = self.board.clear_lines();
+        self.pieces += 1;
+        self.lines += cleared_lines.len() as u32;
         self.update_score(cleared_lines.len() as u32);
-        self.spawn();
+        let clear = self.clear(cleared_lines.len(), t_spin);
+        let attack = self.attack_table.attack(&clear);
+        self.attack += attack;
+        let line_clear = board_before_clear
+            .filter(|_| !cleared_lines.is_empty())
+            .map(|board| LineClear {board, rows: cleared_lines.clone()});
+        self.emit(Event::Lock {cleared_rows: cleared_lines, clear, attack});
+        self.call_mode((), |mode, game| mode.lock(game, &clear));
+        if State::Running != self.state {
+            return;
+        }
+        self.entry_frames = if 0 < clear.lines { self.delays.line_are } else { self.delays.are };
+        if 0 == self.entry_frames {
+            self.spawn();
+        } else {
+            self.line_clear = line_clear;
+        }
     }
 
     pub fn move_left(&mut self) {
-        console::log_1(&"move_left".into());
+        log("move_left");
         let piece = match self.piece.as_mut() {
             None => {
-                console::log_1(&"no piece".into());
+                log("no piece");
                 return;
             },
             Some(piece) => piece,
         };
         piece.move_left();
         if !self.board.is_colliding(&piece) {
-            console::log_1(&"no collision".into());
+            log("no collision");
+            self.last_move_rotation = false;
             return
         }
         piece.move_right();
     }
 
     pub fn move_right(&mut self) {
-        console::log_1(&"move_right".into());
+        log("move_right");
         let piece = match self.piece.as_mut() {
             None => {
-                console::log_1(&"no piece".into());
+                log("no piece");
                 return;
             },
             Some(piece) => piece,
         };
         piece.move_right();
         if !self.board.is_colliding(&piece) {
-            console::log_1(&"no collision".into());
+            log("no collision");
+            self.last_move_rotation = false;
             return
         }
         piece.move_left();
     }
 
     pub fn rotate_clockwise(&mut self) {
-        console::log_1(&"rotate_clockwise".into());
+        log("rotate_clockwise");
         let piece = match self.piece.as_mut() {
             None => {
-                console::log_1(&"no piece".into());
+                log("no piece");
                 return;
             },
             Some(piece) => piece,
         };
         piece.rotate_clockwise();
         if !self.board.is_colliding(&piece) {
-            console::log_1(&"no collision".into());
+            log("no collision");
+            self.last_move_rotation = true;
             return
         }
         piece.rotate_anticlockwise();
     }
 
+    // Inserts garbage rows under the stack, lifting the active piece along.
+    // Tops out when the stack or the piece are pushed out of the board.
+    pub fn push_garbage(&mut self, hole_columns: &[usize]) {
+        let stack_fits = self.board.push_garbage_rows(hole_columns);
+        let piece_fits = match self.piece.as_mut() {
+            None => true,
+            Some(piece) => {
+                piece.move_up(hole_columns.len() as i32);
+                !self.board.is_colliding(piece)
+            },
+        };
+        if !stack_fits || !piece_fits {
+            self.game_over();
+        }
+    }
+
+    // Three corners rule: a T that got in place by a rotation with three of
+    // the four corners around its center occupied. Both corners on the
+    // pointing side make it a full T-spin, otherwise a mini.
+    fn t_spin(&self, piece: &Piece) -> TSpin {
+        if !self.last_move_rotation || !matches!(piece.piece_type(), piece::Type::T) {
+            return TSpin::None;
+        }
+        let is_occupied = |(row, column): (i32, i32)| {
+            self.board.is_occupied(piece.row_offset() + row, piece.column_offset() + column)
+        };
+        let corners = [(0, 0), (0, 2), (2, 0), (2, 2)];
+        if corners.iter().filter(|&&corner| is_occupied(corner)).count() < 3 {
+            return TSpin::None;
+        }
+        let front_corners = match piece.orientation() {
+            Orientation::Top => [(2, 0), (2, 2)],
+            Orientation::Right => [(0, 0), (2, 0)],
+            Orientation::Bottom => [(0, 0), (0, 2)],
+            Orientation::Left => [(0, 2), (2, 2)],
+        };
+        if front_corners.iter().all(|&corner| is_occupied(corner)) {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    fn clear(&mut self, lines: usize, t_spin: TSpin) -> Clear {
+        let mut clear = Clear {
+            lines,
+            t_spin,
+            combo: 0,
+            back_to_back: 0,
+            perfect_clear: lines > 0 && self.board.is_empty(),
+        };
+        if 0 == lines {
+            self.combo = None;
+            return clear;
+        }
+        clear.combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(clear.combo);
+        if clear.is_difficult() {
+            clear.back_to_back = self.back_to_back.map_or(0, |back_to_back| back_to_back + 1);
+            self.back_to_back = Some(clear.back_to_back);
+        } else {
+            self.back_to_back = None;
+        }
+        clear
+    }
+
+    pub fn undo(&mut self) {
+        log("undo");
+        if !self.allows_undo() {
+            return;
+        }
+        if self.history.can_undo() {
+            self.end_entry_delay();
+        }
+        let current = match self.spawn_snapshot.take() {
+            None => return,
+            Some(current) => current,
+        };
+        match self.history.undo(current.clone()) {
+            None => self.spawn_snapshot = Some(current),
+            Some(snapshot) => self.restore(snapshot),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        log("redo");
+        if !self.allows_undo() {
+            return;
+        }
+        if self.history.can_redo() {
+            self.end_entry_delay();
+        }
+        let current = match self.spawn_snapshot.take() {
+            None => return,
+            Some(current) => current,
+        };
+        match self.history.redo(current.clone()) {
+            None => self.spawn_snapshot = Some(current),
+            Some(snapshot) => self.restore(snapshot),
+        }
+    }
+
+    // Only practice modes allow going back, and free play once allowed.
+    pub fn allows_undo(&mut self) -> bool {
+        let undoable = self.undoable;
+        self.call_mode(undoable, |mode, _| mode.allows_undo())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            board: self.board.clone(),
+            piece: self.piece.clone(),
+            next_pieces: self.next_pieces.clone(),
+            generator: self.generator.clone(),
+            score: self.score,
+            lines: self.lines,
+            pieces: self.pieces,
+            attack: self.attack,
+            combo: self.combo,
+            back_to_back: self.back_to_back,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.skip_entry_delay();
+        self.board = snapshot.board.clone();
+        self.piece = snapshot.piece.clone();
+        self.next_pieces = snapshot.next_pieces.clone();
+        self.generator = snapshot.generator.clone();
+        self.score = snapshot.score;
+        self.lines = snapshot.lines;
+        self.pieces = snapshot.pieces;
+        self.attack = snapshot.attack;
+        self.combo = snapshot.combo;
+        self.back_to_back = snapshot.back_to_back;
+        self.last_move_rotation = false;
+        self.pending_rows = 0.;
+        self.spawn_snapshot = Some(snapshot);
+        if State::Over == self.state {
+            self.state = State::Running;
+        }
+    }
+
     pub fn run(&mut self) {
-        console::log_1(&"Run".into());
+        log("Run");
+        self.state = State::Running;
+        self.call_mode((), |mode, game| mode.start(game));
         self.spawn();
     }
 
@@ -122,7 +533,7 @@ impl Game {
 
     fn fill_next_pieces(&mut self) {
         for _ in 0..(self.next_pieces_capacity - self.next_pieces.len()) {
-            console::log_1(&"push new piece".into());
+            log("push new piece");
             let piece = self.create_next_piece();
             self.next_pieces.push_back(piece);
         }
@@ -143,21 +554,111 @@ impl Game {
         piece
     }
 
+    // Spawns the next piece right away if it is still to come, e.g. for the
+    // position it spawns in to be the one undo and redo come back to.
+    fn end_entry_delay(&mut self) {
+        if self.piece.is_none() && 0 < self.entry_frames {
+            self.skip_entry_delay();
+            self.spawn();
+        }
+    }
+
+    fn skip_entry_delay(&mut self) {
+        self.entry_frames = 0;
+        self.line_clear = None;
+    }
+
     fn spawn(&mut self) {
         let piece = self.pop_next_piece();
         let row = self.board.height() as u32 - piece.empty_row_offset();
         let column = self.board.width() as u32 / 2 - piece.horizontal_center_offset();
         self.piece = Some(Piece::new(row as i32, column as i32, piece.piece_type()));
-        console::log_1(&"spawned".into());
+        self.last_move_rotation = false;
+        self.record_spawn();
+        log("spawned");
+    }
+
+    // The position undo goes back to once the piece locks.
+    fn record_spawn(&mut self) {
+        self.spawn_snapshot = if 0 == self.history.capacity() || self.piece.is_none() {
+            None
+        } else {
+            Some(self.snapshot())
+        };
     }
 
     fn game_over(&mut self) {
-        console::log_1(&"game over".into());
+        log("game over");
+        if !self.call_mode(true, |mode, game| mode.top_out(game)) {
+            return;
+        }
+        self.state = State::Over;
+        self.emit(Event::TopOut);
+        self.log_report();
+    }
+
+    // Empties the board and starts again with a fresh piece, keeping the
+    // score and counters.
+    pub fn clear_board(&mut self) {
+        log("clear board");
+        self.board.clear();
+        self.skip_entry_delay();
+        self.piece = None;
+        self.pending_rows = 0.;
+        self.spawn();
+    }
+
+    // Replaces the stack, e.g. with a position pasted by the player, and
+    // goes on with a fresh piece.
+    pub fn set_board(&mut self, board: Board) {
+        log("set board");
+        self.board = board;
+        self.skip_entry_delay();
+        self.piece = None;
+        self.pending_rows = 0.;
+        self.spawn();
+    }
+
+    // Replaces the falling piece, e.g. with the one of a loaded setup.
+    pub fn set_piece(&mut self, piece: Piece) {
+        self.skip_entry_delay();
+        self.piece = Some(piece);
+        self.last_move_rotation = false;
+        self.record_spawn();
+    }
+
+    // Ends the game on a success, e.g. when a mode goal is reached.
+    pub fn finish(&mut self) {
+        log("finished");
+        self.state = State::Finished;
+        self.emit(Event::Finish);
+        self.log_report();
+    }
+
+    fn log_report(&self) {
+        for (label, value) in self.report() {
+            log(&format!("{}: {}", label, value));
+        }
+    }
+
+    // The mode is taken out of the game for the duration of the call so that
+    // it can freely mutate the game it belongs to.
+    fn call_mode<T, F>(&mut self, default: T, f: F) -> T
+    where
+        F: FnOnce(&mut dyn Mode, &mut Game) -> T
+    {
+        let mut mode = match self.mode.take() {
+            None => return default,
+            Some(mode) => mode,
+        };
+        let result = f(mode.as_mut(), self);
+        self.mode = Some(mode);
+        result
     }
 
     fn update_score(&mut self, cleared_lines: u32) {
         self.score += cleared_lines;
-        console::log_1(&format!("score: {}", self.score).into());
+        log(&format!("score: {}", self.score));
     }
 }
 
@@ -170,7 +671,77 @@ impl Game {
         self.piece.as_ref()
     }
 
+    // Where the falling piece would land if dropped now.
+    pub fn ghost_piece(&self) -> Option<Piece> {
+        self.piece.as_ref().map(|piece| self.board.ghost(piece))
+    }
+
+    // Set between a lock clearing lines and the next spawn, when the mode
+    // has a line clear delay.
+    pub fn line_clear(&self) -> Option<&LineClear> {
+        self.line_clear.as_ref()
+    }
+
+    pub fn entry_frames(&self) -> u32 {
+        self.entry_frames
+    }
+
+    pub fn delays(&self) -> Delays {
+        self.delays
+    }
+
     pub fn next_pieces(&self) -> &VecDeque<Piece> {
         &self.next_pieces
     }
+
+    // From now on, events are collected for `take_events`.
+    pub fn record_events(&mut self) {
+        self.records_events = true;
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn emit(&mut self, event: Event) {
+        if self.records_events {
+            self.events.push(event);
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    pub fn pieces(&self) -> u32 {
+        self.pieces
+    }
+
+    pub fn attack(&self) -> u32 {
+        self.attack
+    }
+
+    // Time played, counted in ticks: pieces lock on ticks, so the time of
+    // a sprint or ultra is exact to one tick, 1/60 s, and goes by steps of
+    // 16 or 17 ms.
+    pub fn elapsed_ms(&self) -> u64 {
+        game_loop::ticks_to_ms(self.frame)
+    }
+
+    // The results of the mode, empty in free play.
+    pub fn report(&self) -> Report {
+        self.mode.as_ref().map_or_else(Vec::new, |mode| mode.report(self))
+    }
 }
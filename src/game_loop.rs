@@ -0,0 +1,47 @@
+pub const TICKS_PER_SECOND: u32 = 60;
+
+pub fn ticks_to_ms(ticks: u64) -> u64 {
+    ticks * 1000 / TICKS_PER_SECOND as u64
+}
+
+// Longest frame we agree to catch up on. A tab coming back from background
+// would otherwise ask for thousands of ticks at once.
+const MAX_FRAME_TIME_MS: f64 = 250.;
+
+pub struct FixedTimestep {
+    step: f64,
+    accumulator: f64,
+    last_time: Option<f64>,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u32) -> Self {
+        FixedTimestep {
+            step: 1000. / ticks_per_second as f64,
+            accumulator: 0.,
+            last_time: None,
+        }
+    }
+
+    // Feeds the current time in milliseconds (as given by
+    // `requestAnimationFrame`) and returns how many ticks the simulation
+    // should run to catch up.
+    pub fn advance(&mut self, now: f64) -> u32 {
+        let elapsed = match self.last_time {
+            None => 0.,
+            Some(last_time) => (now - last_time).clamp(0., MAX_FRAME_TIME_MS),
+        };
+        self.last_time = Some(now);
+        self.accumulate(elapsed)
+    }
+
+    pub fn accumulate(&mut self, elapsed: f64) -> u32 {
+        self.accumulator += elapsed;
+        let mut ticks = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+        ticks
+    }
+}
@@ -0,0 +1,158 @@
+use rand::rngs::StdRng;
+use rand::thread_rng;
+use rand::Rng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+// Picks the hole column of successive garbage rows. `messiness` is the
+// chance, between 0 and 1, that a row does not reuse the previous hole.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "HoleGeneratorState", from = "HoleGeneratorState")]
+pub struct HoleGenerator {
+    width: usize,
+    messiness: f64,
+    hole_column: Option<usize>,
+    rng: StdRng,
+    seed: u64,
+    rows: u32,
+}
+
+// Saved as its seed, the rows it picked a hole for are picked again on
+// load.
+#[derive(Serialize, Deserialize)]
+struct HoleGeneratorState {
+    width: usize,
+    messiness: f64,
+    seed: u64,
+    rows: u32,
+}
+
+impl From<HoleGenerator> for HoleGeneratorState {
+    fn from(holes: HoleGenerator) -> Self {
+        HoleGeneratorState {width: holes.width, messiness: holes.messiness, seed: holes.seed, rows: holes.rows}
+    }
+}
+
+impl From<HoleGeneratorState> for HoleGenerator {
+    fn from(state: HoleGeneratorState) -> Self {
+        let mut holes = HoleGenerator::with_seed(state.width, state.messiness, state.seed);
+        holes.next_hole_columns(state.rows as usize);
+        holes
+    }
+}
+
+impl HoleGenerator {
+    pub fn new(width: usize, messiness: f64) -> Self {
+        Self::with_seed(width, messiness, thread_rng().gen())
+    }
+
+    pub fn with_seed(width: usize, messiness: f64, seed: u64) -> Self {
+        HoleGenerator {
+            width,
+            messiness: messiness.clamp(0., 1.),
+            hole_column: None,
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+            rows: 0,
+        }
+    }
+
+    pub fn next_hole_column(&mut self) -> usize {
+        let rng = &mut self.rng;
+        let hole_column = match self.hole_column {
+            Some(hole_column) if !rng.gen_bool(self.messiness) => hole_column,
+            Some(hole_column) if self.width > 1 => {
+                let column = rng.gen_range(0..self.width - 1);
+                if column >= hole_column { column + 1 } else { column }
+            },
+            _ => rng.gen_range(0..self.width),
+        };
+        self.hole_column = Some(hole_column);
+        self.rows += 1;
+        hole_column
+    }
+
+    pub fn next_hole_columns(&mut self, count: usize) -> Vec<usize> {
+        (0..count).map(|_| self.next_hole_column()).collect()
+    }
+}
+
+#[derive(Clone)]
+struct IncomingGarbage {
+    lines: u32,
+    remaining_ticks: u32,
+}
+
+// Garbage received but not yet inserted. Each batch waits `delay_ticks`
+// before it can enter the board, and outgoing attack cancels the oldest
+// batches first.
+#[derive(Clone)]
+pub struct GarbageQueue {
+    delay_ticks: u32,
+    incoming: VecDeque<IncomingGarbage>,
+}
+
+impl GarbageQueue {
+    pub fn new(delay_ticks: u32) -> Self {
+        GarbageQueue {
+            delay_ticks,
+            incoming: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, lines: u32) {
+        if 0 == lines {
+            return;
+        }
+        self.incoming.push_back(IncomingGarbage {lines, remaining_ticks: self.delay_ticks});
+    }
+
+    // Uses `attack` to cancel pending garbage, returns what is left of it.
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while attack > 0 {
+            let garbage = match self.incoming.front_mut() {
+                None => break,
+                Some(garbage) => garbage,
+            };
+            let cancelled = garbage.lines.min(attack);
+            garbage.lines -= cancelled;
+            attack -= cancelled;
+            if 0 == garbage.lines {
+                self.incoming.pop_front();
+            }
+        }
+        attack
+    }
+
+    pub fn tick(&mut self) {
+        for garbage in self.incoming.iter_mut() {
+            garbage.remaining_ticks = garbage.remaining_ticks.saturating_sub(1);
+        }
+    }
+
+    pub fn pending_lines(&self) -> u32 {
+        self.incoming.iter().map(|garbage| garbage.lines).sum()
+    }
+
+    pub fn ready_lines(&self) -> u32 {
+        self.incoming.iter()
+            .filter(|garbage| 0 == garbage.remaining_ticks)
+            .map(|garbage| garbage.lines)
+            .sum()
+    }
+
+    // Removes and returns the batches whose delay is over, oldest first.
+    pub fn take_ready(&mut self) -> Vec<u32> {
+        let mut ready = Vec::new();
+        while let Some(garbage) = self.incoming.front() {
+            if garbage.remaining_ticks > 0 {
+                break;
+            }
+            ready.push(garbage.lines);
+            self.incoming.pop_front();
+        }
+        ready
+    }
+}
@@ -0,0 +1,13 @@
+use super::game_loop::TICKS_PER_SECOND;
+
+// Fastest possible fall: the whole visible board in a single tick.
+pub const MAX_ROWS_PER_TICK: f64 = 20.;
+
+// Guideline speed curve: seconds per row is (0.8 - (level - 1) * 0.007)^(level - 1).
+// Returned in rows per tick so that levels past 20G keep a meaningful value.
+pub fn guideline_rows_per_tick(level: u32) -> f64 {
+    let level = level.max(1) as f64;
+    let seconds_per_row = (0.8 - (level - 1.) * 0.007).powf(level - 1.);
+    let rows_per_tick = 1. / (seconds_per_row * TICKS_PER_SECOND as f64);
+    rows_per_tick.min(MAX_ROWS_PER_TICK)
+}
@@ -0,0 +1,59 @@
+use std::collections::VecDeque;
+
+// Bounded undo/redo stacks. The oldest entries are dropped once `capacity`
+// is reached.
+#[derive(Clone)]
+pub struct History<T> {
+    capacity: usize,
+    undo_stack: VecDeque<T>,
+    redo_stack: Vec<T>,
+}
+
+impl<T> History<T> {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity,
+            undo_stack: VecDeque::with_capacity(capacity),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Records a new state to go back to, forgetting the undone ones. Nothing
+    // is kept with a capacity of 0.
+    pub fn push(&mut self, entry: T) {
+        if 0 == self.capacity {
+            return;
+        }
+        if self.undo_stack.len() == self.capacity {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(entry);
+        self.redo_stack.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // Returns the previous state, `current` becomes redoable.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let entry = self.undo_stack.pop_back()?;
+        self.redo_stack.push(current);
+        Some(entry)
+    }
+
+    // Returns the last undone state, `current` becomes undoable again.
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let entry = self.redo_stack.pop()?;
+        self.undo_stack.push_back(current);
+        Some(entry)
+    }
+}
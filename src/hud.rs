@@ -0,0 +1,72 @@
+use super::game::Game;
+use super::mode;
+
+// Values shown beside the board, each in the page element of the same id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Score,
+    Lines,
+    Level,
+    Time,
+    PiecesPerSecond,
+    AttackPerMinute,
+}
+
+pub const FIELDS: [Field; 6] = [
+    Field::Score,
+    Field::Lines,
+    Field::Level,
+    Field::Time,
+    Field::PiecesPerSecond,
+    Field::AttackPerMinute,
+];
+
+impl Field {
+    pub fn id(self) -> &'static str {
+        match self {
+            Field::Score => "score",
+            Field::Lines => "lines",
+            Field::Level => "level",
+            Field::Time => "time",
+            Field::PiecesPerSecond => "pps",
+            Field::AttackPerMinute => "apm",
+        }
+    }
+
+    pub fn text(self, game: &Game) -> String {
+        match self {
+            Field::Score => game.score().to_string(),
+            Field::Lines => game.lines().to_string(),
+            Field::Level => game.level().to_string(),
+            Field::Time => mode::format_time(game.elapsed_ms()),
+            Field::PiecesPerSecond => format!("{:.2}", mode::pieces_per_second(game)),
+            Field::AttackPerMinute => format!("{:.1}", mode::attack_per_minute(game)),
+        }
+    }
+}
+
+// Remembers what is shown so that only the values that changed are written
+// to the page, every frame.
+#[derive(Default)]
+pub struct Hud {
+    shown: [Option<String>; FIELDS.len()],
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The fields to show again, with their new text.
+    pub fn update(&mut self, game: &Game) -> Vec<(Field, String)> {
+        let mut changes = Vec::new();
+        for (&field, shown) in FIELDS.iter().zip(self.shown.iter_mut()) {
+            let text = field.text(game);
+            if shown.as_ref() != Some(&text) {
+                *shown = Some(text.clone());
+                changes.push((field, text));
+            }
+        }
+        changes
+    }
+}
@@ -0,0 +1,177 @@
+//! Frames drawn again only where they changed since the previous one.
+
+use super::draw::Renderer;
+use super::theme::CellStyle;
+use super::theme::Theme;
+
+use std::mem;
+
+// Drawn below the cells, the grid layer.
+#[derive(Clone, Debug, PartialEq)]
+enum Layer {
+    Background(String),
+    Grid(String),
+    HorizontalLine(usize, String),
+}
+
+// Drawn in a cell, over what was drawn in it before.
+#[derive(Clone, Debug, PartialEq)]
+enum Paint {
+    Cell(String, CellStyle),
+    Glyph(char, String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Frame {
+    rows: usize,
+    columns: usize,
+    layers: Vec<Layer>,
+    // Row after row from the bottom.
+    cells: Vec<Vec<Paint>>,
+    texts: Vec<(usize, String, String)>,
+}
+
+impl Frame {
+    fn new(rows: usize, columns: usize) -> Self {
+        Frame {rows, columns, layers: Vec::new(), cells: vec![Vec::new(); rows * columns], texts: Vec::new()}
+    }
+
+    fn paint(&mut self, row: usize, column: usize, paint: Paint) {
+        if row < self.rows && column < self.columns {
+            self.cells[row * self.columns + column].push(paint);
+        }
+    }
+
+    // Texts run over several cells and the lines between them: a frame
+    // with text is drawn in full unless it did not change at all.
+    fn can_update(&self, shown: &Frame) -> bool {
+        (self.rows, self.columns, &self.layers, &self.texts) == (shown.rows, shown.columns, &shown.layers, &shown.texts)
+            && (self.texts.is_empty() || self.cells == shown.cells)
+    }
+
+    // Cells have nothing else under them than the background, the lines of
+    // the grid layer staying as they are around them.
+    fn background(&self, theme: &Theme) -> String {
+        self.layers.iter().rev()
+            .find_map(|layer| match layer {
+                Layer::Background(color) => Some(color.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| theme.background.clone())
+    }
+}
+
+fn draw_paints(renderer: &mut impl Renderer, row: usize, column: usize, paints: &[Paint]) {
+    for paint in paints {
+        match paint {
+            Paint::Cell(color, style) => renderer.styled_cell(row, column, color, *style),
+            Paint::Glyph(glyph, color) => renderer.glyph(row, column, *glyph, color),
+        }
+    }
+}
+
+// Keeps what the frames drawn through it are made of, and draws on the
+// renderer it wraps only the cells that changed since the last frame. The
+// background, grid and limit line are only drawn again with the text or
+// when they change themselves.
+pub struct IncrementalRenderer<R> {
+    renderer: R,
+    frame: Frame,
+    shown: Option<Frame>,
+}
+
+impl<R: Renderer> IncrementalRenderer<R> {
+    pub fn new(renderer: R) -> Self {
+        IncrementalRenderer {renderer, frame: Frame::new(0, 0), shown: None}
+    }
+
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
+    // The next frame is drawn in full, as changing the renderer may change
+    // how everything looks.
+    pub fn renderer_mut(&mut self) -> &mut R {
+        self.shown = None;
+        &mut self.renderer
+    }
+
+    fn draw_all(&mut self) {
+        let frame = &self.frame;
+        let renderer = &mut self.renderer;
+        renderer.clear(frame.rows, frame.columns);
+        for layer in &frame.layers {
+            match layer {
+                Layer::Background(color) => renderer.background(color),
+                Layer::Grid(color) => renderer.grid(color),
+                Layer::HorizontalLine(row, color) => renderer.horizontal_line(*row, color),
+            }
+        }
+        for (index, paints) in frame.cells.iter().enumerate() {
+            draw_paints(renderer, index / frame.columns, index % frame.columns, paints);
+        }
+        for (row, text, color) in &frame.texts {
+            renderer.text(*row, text, color);
+        }
+    }
+
+    fn draw_changes(&mut self, shown: &Frame) {
+        let frame = &self.frame;
+        let renderer = &mut self.renderer;
+        let background = frame.background(renderer.theme());
+        let changes = frame.cells.iter().zip(shown.cells.iter()).enumerate()
+            .filter(|(_, (paints, shown_paints))| paints != shown_paints);
+        for (index, (paints, _)) in changes {
+            let (row, column) = (index / frame.columns, index % frame.columns);
+            renderer.cell(row, column, &background);
+            draw_paints(renderer, row, column, paints);
+        }
+    }
+}
+
+impl<R: Renderer> Renderer for IncrementalRenderer<R> {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.frame = Frame::new(rows, columns);
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        self.frame.paint(row, column, Paint::Cell(color.to_string(), CellStyle::Flat));
+    }
+
+    fn grid(&mut self, color: &str) {
+        self.frame.layers.push(Layer::Grid(color.to_string()));
+    }
+
+    fn horizontal_line(&mut self, row: usize, color: &str) {
+        self.frame.layers.push(Layer::HorizontalLine(row, color.to_string()));
+    }
+
+    fn text(&mut self, row: usize, text: &str, color: &str) {
+        self.frame.texts.push((row, text.to_string(), color.to_string()));
+    }
+
+    fn theme(&self) -> &Theme {
+        self.renderer.theme()
+    }
+
+    fn background(&mut self, color: &str) {
+        self.frame.layers.push(Layer::Background(color.to_string()));
+    }
+
+    fn styled_cell(&mut self, row: usize, column: usize, color: &str, style: CellStyle) {
+        self.frame.paint(row, column, Paint::Cell(color.to_string(), style));
+    }
+
+    fn glyph(&mut self, row: usize, column: usize, glyph: char, color: &str) {
+        self.frame.paint(row, column, Paint::Glyph(glyph, color.to_string()));
+    }
+
+    fn end_frame(&mut self) {
+        match self.shown.take() {
+            Some(shown) if self.frame.can_update(&shown) => self.draw_changes(&shown),
+            _ => self.draw_all(),
+        }
+        self.renderer.end_frame();
+        self.shown = Some(mem::replace(&mut self.frame, Frame::new(0, 0)));
+    }
+}
@@ -0,0 +1,56 @@
+use super::game::Game;
+
+use std::ops::BitOr;
+use std::ops::BitOrAssign;
+
+// Actions pressed by a player during one frame, one bit per action.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Input(u8);
+
+impl Input {
+    pub const NONE: Input = Input(0);
+    pub const LEFT: Input = Input(1);
+    pub const RIGHT: Input = Input(1 << 1);
+    pub const DOWN: Input = Input(1 << 2);
+    pub const ROTATE: Input = Input(1 << 3);
+
+    pub fn from_bits(bits: u8) -> Self {
+        Input(bits)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: Input) -> bool {
+        other.0 == self.0 & other.0
+    }
+}
+
+impl BitOr for Input {
+    type Output = Self;
+    fn bitor(self, other: Input) -> Input {
+        Input(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Input {
+    fn bitor_assign(&mut self, other: Input) {
+        self.0 |= other.0;
+    }
+}
+
+pub fn apply(game: &mut Game, input: Input) {
+    if input.contains(Input::LEFT) {
+        game.move_left();
+    }
+    if input.contains(Input::RIGHT) {
+        game.move_right();
+    }
+    if input.contains(Input::ROTATE) {
+        game.rotate_clockwise();
+    }
+    if input.contains(Input::DOWN) {
+        game.move_down();
+    }
+}
@@ -1,17 +1,50 @@
 mod utils;
-mod board;
-mod draw;
-mod piece;
-mod game;
+pub mod board;
+pub mod draw;
+pub mod piece;
+pub mod game;
+pub mod fumen;
+pub mod save;
+pub mod theme;
+pub mod raster;
+pub mod replay;
+pub mod svg;
+pub mod game_loop;
+pub mod hud;
+pub mod incremental;
+pub mod garbage;
+mod gravity;
+mod history;
+pub mod attack;
+pub mod input;
+pub mod rollback;
+pub mod protocol;
+mod online;
+pub mod mode;
 mod piece_type_bag_generator;
+pub mod versus;
 
 extern crate web_sys;
 
-use game::Game;
+use attack::Guideline;
+use board::Board;
+use game_loop::FixedTimestep;
+use game_loop::TICKS_PER_SECOND;
+use hud::Hud;
+use input::Input;
+use online::Online;
+use theme::Theme;
+use utils::log;
+use versus::Versus;
 use draw::draw_board;
+use draw::draw_garbage_meter;
 use draw::draw_next_pieces;
+use draw::CanvasRenderer;
+use incremental::IncrementalRenderer;
 
+use std::collections::VecDeque;
 use std::rc::Rc;
+use std::cell::Cell;
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -28,7 +61,7 @@ fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
+fn request_animation_frame(f: &Closure<dyn FnMut(f64)>) {
     window()
         .request_animation_frame(f.as_ref().unchecked_ref())
         .expect("should register `requestAnimationFrame` OK");
@@ -40,14 +73,112 @@ fn document() -> web_sys::Document {
         .expect("should have a document on window")
 }
 
+const SAVE_KEY: &str = "tetris-save";
+
+fn local_storage() -> Option<web_sys::Storage> {
+    window().local_storage().ok().flatten()
+}
+
 #[wasm_bindgen]
-pub fn run() -> Result<(), JsValue> {
+pub fn has_saved_game() -> bool {
+    local_storage().and_then(|storage| storage.get_item(SAVE_KEY).ok().flatten()).is_some()
+}
+
+// A position given as a fumen (its first page) or as a board in text, the
+// way they can be pasted into the game.
+fn parse_position(position: &str) -> Result<(Board, Option<piece::Piece>), JsValue> {
+    if position.contains("v115@") {
+        let pages = fumen::decode(position).map_err(|error| JsValue::from_str(&error.to_string()))?;
+        let page = pages.into_iter().next().ok_or_else(|| JsValue::from_str("fumen without pages"))?;
+        return Ok((page.board, page.piece));
+    }
+    let board = Board::from_text(position, 20, 10).map_err(|error| JsValue::from_str(&error.to_string()))?;
+    Ok((board, None))
+}
+
+#[wasm_bindgen]
+pub fn position_svg(position: &str, ghost: bool, theme: Option<String>) -> Result<String, JsValue> {
+    let (board, piece) = parse_position(position)?;
+    Ok(svg::board_to_svg(&board, piece.as_ref(), ghost, &parse_theme(theme)))
+}
+
+#[wasm_bindgen]
+pub fn position_png(position: &str, ghost: bool, theme: Option<String>) -> Result<Vec<u8>, JsValue> {
+    let (board, piece) = parse_position(position)?;
+    Ok(raster::board_to_png(&board, piece.as_ref(), ghost, &parse_theme(theme)))
+}
+
+// A built-in theme by name, or one in JSON. Anything else falls back to the
+// classic theme.
+fn parse_theme(theme: Option<String>) -> Theme {
+    let theme = match theme {
+        Some(theme) => theme,
+        None => return Theme::classic(),
+    };
+    if theme.trim_start().starts_with('{') {
+        return Theme::from_json(&theme).unwrap_or_else(|error| {
+            log(&format!("theme ignored: {}", error));
+            Theme::classic()
+        });
+    }
+    theme::from_name(&theme).unwrap_or_else(|| {
+        log(&format!("unknown theme {}", theme));
+        Theme::classic()
+    })
+}
+
+// Keeps the game in progress for later, a finished one is forgotten.
+fn save_game(game: &game::Game) {
+    let storage = match local_storage() {
+        Some(storage) => storage,
+        None => return,
+    };
+    let result = match game.state() {
+        game::State::Ready => return,
+        game::State::Running | game::State::Paused => storage.set_item(SAVE_KEY, &save::save(game)),
+        game::State::Over | game::State::Finished => storage.remove_item(SAVE_KEY),
+    };
+    if result.is_err() {
+        log("could not save the game");
+    }
+}
+
+fn load_saved_game() -> Option<game::Game> {
+    let text = local_storage()?.get_item(SAVE_KEY).ok()??;
+    match save::load(&text) {
+        Ok(game) => Some(game),
+        Err(error) => {
+            log(&format!("saved game ignored: {}", error));
+            None
+        },
+    }
+}
+
+// A fumen, if any, sets up the board and piece to start from. Resuming
+// picks the saved game up paused instead, mode included.
+#[wasm_bindgen]
+pub fn run(mode: Option<String>, fumen: Option<String>, resume: bool, theme: Option<String>) -> Result<(), JsValue> {
     let document = document();
 
-    let game = Rc::new(RefCell::new(game::Game::new()));
+    let saved_game = if resume { load_saved_game() } else { None };
+    let is_resumed = saved_game.is_some();
+    let game = match (saved_game, mode.as_deref().and_then(mode::from_name)) {
+        (Some(game), _) => game,
+        (None, None) => {
+            let mut game = game::Game::new();
+            game.allow_undo();
+            game
+        },
+        (None, Some(mode)) => game::Game::with_mode(mode),
+    };
+    let game = Rc::new(RefCell::new(game));
     {
         let game = Rc::clone(&game);
-        bind_key(&document, " ", move || {game.borrow_mut().pause()})?;
+        bind_key(&document, " ", move || {
+            let mut game = game.borrow_mut();
+            game.pause();
+            save_game(&game);
+        })?;
     }
     {
         let game = Rc::clone(&game);
@@ -65,8 +196,212 @@ pub fn run() -> Result<(), JsValue> {
         let game = Rc::clone(&game);
         bind_key(&document, "ArrowRight", move || {game.borrow_mut().move_right()})?;
     }
-    Rc::clone(&game).borrow_mut().run();
-    setup_draw(Rc::clone(&game));
+    {
+        let game = Rc::clone(&game);
+        bind_key(&document, "u", move || {game.borrow_mut().undo()})?;
+    }
+    {
+        let game = Rc::clone(&game);
+        bind_key(&document, "r", move || {game.borrow_mut().redo()})?;
+    }
+    // Setting the board up is practice, as going back is.
+    let allows_setup = game.borrow_mut().allows_undo();
+    let pages = Rc::new(RefCell::new(VecDeque::new()));
+    if allows_setup {
+        bind_paste(&document, &game, &pages)?;
+        let game = Rc::clone(&game);
+        let pages = Rc::clone(&pages);
+        bind_key(&document, "n", move || load_next_page(&mut game.borrow_mut(), &mut pages.borrow_mut()))?;
+    }
+    {
+        let game = Rc::clone(&game);
+        bind_key(&document, "c", move || copy_fumen(&game.borrow()))?;
+    }
+    {
+        let game = Rc::clone(&game);
+        let closure = Closure::wrap(Box::new(move || save_game(&game.borrow())) as Box<dyn FnMut()>);
+        window().add_event_listener_with_callback("pagehide", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+    }
+    if !is_resumed {
+        Rc::clone(&game).borrow_mut().run();
+        if let Some(fumen) = fumen.filter(|_| allows_setup) {
+            load_fumen(&mut game.borrow_mut(), &mut pages.borrow_mut(), &fumen);
+        }
+    }
+    let theme = parse_theme(theme);
+    let mut board_renderer = IncrementalRenderer::new(CanvasRenderer::new(get_context("board"), draw::ZOOM).with_theme(theme.clone()));
+    let mut next_renderer = IncrementalRenderer::new(CanvasRenderer::new(get_context("next"), 15).with_theme(theme));
+    let mut hud = Hud::new();
+    let resized = watch_resize()?;
+    let draw_game = Rc::clone(&game);
+    setup_main_loop(
+        move || game.borrow_mut().tick(),
+        move || {
+            if resized.replace(false) {
+                fit_player("board", draw_game.borrow().board(), board_renderer.renderer_mut(), next_renderer.renderer_mut(), None);
+            }
+            draw_board(&draw_game.borrow(), &mut board_renderer);
+            draw_next_pieces(&draw_game.borrow(), &mut next_renderer);
+            show_hud(&mut hud, &draw_game.borrow());
+            show_report(&draw_game.borrow());
+        },
+    );
+    Ok(())
+}
+
+// Two players on one keyboard: WASD on the left board, arrows on the right.
+#[wasm_bindgen]
+pub fn run_versus(attack_table: Option<String>, theme: Option<String>) -> Result<(), JsValue> {
+    let document = document();
+    for id in ["garbage", "opponent"].iter() {
+        document.get_element_by_id(id).unwrap().remove_attribute("hidden")?;
+    }
+
+    let attack_table = attack_table.as_deref()
+        .and_then(attack::table_from_name)
+        .unwrap_or_else(|| Box::new(Guideline));
+    let versus = Versus::new(attack_table, versus::DEFAULT_GARBAGE_DELAY_TICKS);
+    let versus = Rc::new(RefCell::new(versus));
+    {
+        let versus = Rc::clone(&versus);
+        bind_key(&document, " ", move || {versus.borrow_mut().pause()})?;
+    }
+    bind_player_keys(&document, &versus, 0, ["s", "w", "a", "d"])?;
+    bind_player_keys(&document, &versus, 1, ["ArrowDown", "ArrowUp", "ArrowLeft", "ArrowRight"])?;
+    Rc::clone(&versus).borrow_mut().run();
+    let theme = parse_theme(theme);
+    let mut renderers = [
+        player_renderers(BOARD_IDS[0], "next", "garbage", &theme),
+        player_renderers(BOARD_IDS[1], "opponent-next", "opponent-garbage", &theme),
+    ];
+    let mut hud = Hud::new();
+    let resized = watch_resize()?;
+    let draw_versus = Rc::clone(&versus);
+    setup_main_loop(
+        move || versus.borrow_mut().tick(),
+        move || {
+            let versus = draw_versus.borrow();
+            let resized = resized.replace(false);
+            for (player, (board_renderer, next_renderer, garbage_renderer)) in renderers.iter_mut().enumerate() {
+                let game = versus.game(player);
+                if resized {
+                    fit_player(BOARD_IDS[player], game.board(), board_renderer.renderer_mut(), next_renderer.renderer_mut(), Some(garbage_renderer.renderer_mut()));
+                }
+                draw_garbage_meter(versus.garbage_queue(player), game.board(), garbage_renderer);
+                draw_board(game, board_renderer);
+                draw_next_pieces(game, next_renderer);
+            }
+            show_hud(&mut hud, versus.game(0));
+        },
+    );
+    Ok(())
+}
+
+// Versus against another browser through the match server at `url`, see
+// the `tetris-server` binary. Both players use the arrows.
+#[wasm_bindgen]
+pub fn run_online(url: String, room: String, name: String, theme: Option<String>) -> Result<(), JsValue> {
+    let document = document();
+    for id in ["garbage", "opponent"].iter() {
+        document.get_element_by_id(id).unwrap().remove_attribute("hidden")?;
+    }
+
+    let online = Rc::new(RefCell::new(Online::connect(&url, &room, &name)?));
+    let input = Rc::new(Cell::new(Input::NONE));
+    for &(key, action) in [
+        ("ArrowDown", Input::DOWN),
+        ("ArrowUp", Input::ROTATE),
+        ("ArrowLeft", Input::LEFT),
+        ("ArrowRight", Input::RIGHT),
+    ].iter() {
+        let input = Rc::clone(&input);
+        bind_key(&document, key, move || {input.set(input.get() | action)})?;
+    }
+    let theme = parse_theme(theme);
+    let mut renderers = [
+        player_renderers(BOARD_IDS[0], "next", "garbage", &theme),
+        player_renderers(BOARD_IDS[1], "opponent-next", "opponent-garbage", &theme),
+    ];
+    let mut hud = Hud::new();
+    let resized = watch_resize()?;
+    let draw_online = Rc::clone(&online);
+    setup_main_loop(
+        move || online.borrow_mut().tick(input.replace(Input::NONE)),
+        move || {
+            let online = draw_online.borrow();
+            let (versus, local_player) = match (online.versus(), online.local_player()) {
+                (Some(versus), Some(local_player)) => (versus, local_player),
+                _ => return,
+            };
+            let players = [local_player, 1 - local_player];
+            let resized = resized.replace(false);
+            for ((&player, board_id), (board_renderer, next_renderer, garbage_renderer)) in players.iter().zip(BOARD_IDS.iter()).zip(renderers.iter_mut()) {
+                let game = versus.game(player);
+                if resized {
+                    fit_player(board_id, game.board(), board_renderer.renderer_mut(), next_renderer.renderer_mut(), Some(garbage_renderer.renderer_mut()));
+                }
+                draw_garbage_meter(versus.garbage_queue(player), game.board(), garbage_renderer);
+                draw_board(game, board_renderer);
+                draw_next_pieces(game, next_renderer);
+            }
+            show_hud(&mut hud, versus.game(local_player));
+            if let Some(winner) = online.winner() {
+                show_result(winner == local_player);
+            }
+        },
+    );
+    Ok(())
+}
+
+// Tells the local player how the match ended.
+fn show_result(won: bool) {
+    let element = match document().get_element_by_id("result") {
+        Some(element) => element,
+        None => return,
+    };
+    if element.has_attribute("hidden") {
+        element.set_text_content(Some(if won { "You win" } else { "You lose" }));
+        let _ = element.remove_attribute("hidden");
+    }
+}
+
+// Lists the results of the mode once the game has ended, free play has none.
+fn show_report(game: &game::Game) {
+    if let game::State::Ready | game::State::Running | game::State::Paused = game.state() {
+        return;
+    }
+    let element = match document().get_element_by_id("result") {
+        Some(element) => element,
+        None => return,
+    };
+    let report = game.report();
+    if element.has_attribute("hidden") && !report.is_empty() {
+        let lines: Vec<String> = report.iter().map(|(label, value)| format!("{}: {}", label, value)).collect();
+        element.set_text_content(Some(&lines.join("\n")));
+        let _ = element.remove_attribute("hidden");
+    }
+}
+
+// Keys are given in down, rotate, left, right order.
+fn bind_player_keys(document: &Document, versus: &Rc<RefCell<Versus>>, player: usize, keys: [&'static str; 4]) -> Result<(), JsValue> {
+    let [down, rotate, left, right] = keys;
+    {
+        let versus = Rc::clone(versus);
+        bind_key(document, down, move || {versus.borrow_mut().game_mut(player).move_down()})?;
+    }
+    {
+        let versus = Rc::clone(versus);
+        bind_key(document, rotate, move || {versus.borrow_mut().game_mut(player).rotate_clockwise()})?;
+    }
+    {
+        let versus = Rc::clone(versus);
+        bind_key(document, left, move || {versus.borrow_mut().game_mut(player).move_left()})?;
+    }
+    {
+        let versus = Rc::clone(versus);
+        bind_key(document, right, move || {versus.borrow_mut().game_mut(player).move_right()})?;
+    }
     Ok(())
 }
 
@@ -75,7 +410,7 @@ where
     F: Fn() + 'static
 {
     let closure = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
-        if key_name != event.key() {
+        if key_name != event.key() || is_typing(&event) {
             return;
         }
         action();
@@ -86,17 +421,153 @@ where
     Ok(())
 }
 
-fn setup_draw(game: Rc<RefCell<Game>>) {
-    let board_context = get_context("board");
-    let next_context = get_context("next");
-    let draw_func = Rc::new(RefCell::new(None));
-    let init_draw_func = Rc::clone(&draw_func);
-    *init_draw_func.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        draw_board(&game.borrow(), &board_context, 30);
-        draw_next_pieces(&game.borrow(), &next_context, 15);
-        request_animation_frame(draw_func.borrow().as_ref().unwrap());
-    }) as Box<dyn FnMut()>));
-    request_animation_frame(init_draw_func.borrow().as_ref().unwrap());
+// Keys typed in form fields are text, not shortcuts.
+fn is_typing(event: &web_sys::KeyboardEvent) -> bool {
+    event.target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|element| matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA"))
+}
+
+// Pasting a fumen or a board in the text format of `Board::from_text`
+// replaces the stack of the game.
+fn bind_paste(document: &Document, game: &Rc<RefCell<game::Game>>, pages: &FumenPages) -> Result<(), JsValue> {
+    let game = Rc::clone(game);
+    let pages = Rc::clone(pages);
+    let closure = Closure::wrap(Box::new(move |event: web_sys::ClipboardEvent| {
+        let text = match event.clipboard_data().and_then(|data| data.get_data("text").ok()) {
+            Some(text) => text,
+            None => return,
+        };
+        if text.contains("v115@") {
+            load_fumen(&mut game.borrow_mut(), &mut pages.borrow_mut(), &text);
+            return;
+        }
+        let (height, width) = (game.borrow().board().height(), game.borrow().board().width());
+        match Board::from_text(&text, height, width) {
+            Ok(board) => game.borrow_mut().set_board(board),
+            Err(error) => log(&format!("pasted board ignored: {}", error)),
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    document.add_event_listener_with_callback("paste", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(())
+}
+
+// Pages of the last fumen loaded, after the one the game is set up from.
+type FumenPages = Rc<RefCell<VecDeque<fumen::Page>>>;
+
+// Puts the board and the falling piece on the clipboard as a fumen.
+fn copy_fumen(game: &game::Game) {
+    let page = fumen::Page {board: game.board().clone(), piece: game.piece().cloned(), comment: String::new()};
+    let _ = window().navigator().clipboard().write_text(&fumen::encode(&[page]));
+}
+
+// Sets up the game from the first page of a fumen, the others coming with
+// `load_next_page`.
+fn load_fumen(game: &mut game::Game, pages: &mut VecDeque<fumen::Page>, text: &str) {
+    match fumen::decode(text) {
+        Ok(decoded) => *pages = decoded.into(),
+        Err(error) => {
+            log(&format!("fumen ignored: {}", error));
+            return;
+        },
+    }
+    if 1 < pages.len() {
+        log(&format!("fumen of {} pages, press n for the next one", pages.len()));
+    }
+    load_next_page(game, pages);
+}
+
+fn load_next_page(game: &mut game::Game, pages: &mut VecDeque<fumen::Page>) {
+    if let Some(page) = pages.pop_front() {
+        game.set_board(page.board);
+        if let Some(piece) = page.piece {
+            game.set_piece(piece);
+        }
+    }
+}
+
+// The game logic runs at a fixed rate whatever the display refresh rate is:
+// each animation frame catches up on the elapsed ticks, then draws once.
+fn setup_main_loop<T, D>(mut tick: T, mut draw: D)
+where
+    T: FnMut() + 'static,
+    D: FnMut() + 'static,
+{
+    let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+    let loop_func = Rc::new(RefCell::new(None));
+    let init_loop_func = Rc::clone(&loop_func);
+    *init_loop_func.borrow_mut() = Some(Closure::wrap(Box::new(move |now: f64| {
+        for _ in 0..timestep.advance(now) {
+            tick();
+        }
+        draw();
+        request_animation_frame(loop_func.borrow().as_ref().unwrap());
+    }) as Box<dyn FnMut(f64)>));
+    request_animation_frame(init_loop_func.borrow().as_ref().unwrap());
+}
+
+// Writes the values of the sidebar that changed, those missing from the page
+// are skipped.
+fn show_hud(hud: &mut Hud, game: &game::Game) {
+    let document = document();
+    for (field, text) in hud.update(game) {
+        if let Some(element) = document.get_element_by_id(field.id()) {
+            element.set_text_content(Some(&text));
+        }
+    }
+}
+
+// Set when the window is resized, as it also is when the pixel ratio changes
+// with the browser zoom, and to start with: the canvases are to be laid out.
+fn watch_resize() -> Result<Rc<Cell<bool>>, JsValue> {
+    let resized = Rc::new(Cell::new(true));
+    let set_resized = Rc::clone(&resized);
+    let closure = Closure::wrap(Box::new(move || set_resized.set(true)) as Box<dyn FnMut()>);
+    window().add_event_listener_with_callback("resize", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+    Ok(resized)
+}
+
+// Canvases of the boards of the players, the left one first.
+const BOARD_IDS: [&str; 2] = ["board", "opponent-board"];
+
+// Zooms the board of a player to fill as much of the element around its
+// canvas as it can, in pixels of the screen. The garbage meter beside it
+// takes the same zoom and the next pieces half of it.
+fn fit_player(board_id: &str, board: &Board, board_renderer: &mut CanvasRenderer, next_renderer: &mut CanvasRenderer, garbage_renderer: Option<&mut CanvasRenderer>) {
+    let frame = match document().get_element_by_id(board_id).and_then(|canvas| canvas.parent_element()) {
+        Some(frame) => frame,
+        None => return,
+    };
+    let pixel_ratio = window().device_pixel_ratio();
+    let columns = board.width() + if garbage_renderer.is_some() { 1 } else { 0 };
+    let zoom = draw::fit_zoom(
+        board.height() + board.max_piece_size(),
+        columns,
+        f64::from(frame.client_width()) * pixel_ratio,
+        f64::from(frame.client_height()) * pixel_ratio,
+    );
+    board_renderer.set_zoom(zoom);
+    board_renderer.set_pixel_ratio(pixel_ratio);
+    next_renderer.set_zoom(zoom / 2);
+    next_renderer.set_pixel_ratio(pixel_ratio);
+    if let Some(garbage_renderer) = garbage_renderer {
+        garbage_renderer.set_zoom(zoom);
+        garbage_renderer.set_pixel_ratio(pixel_ratio);
+    }
+}
+
+// Canvases only drawn on where frames change.
+type PlayerRenderer = IncrementalRenderer<CanvasRenderer>;
+
+fn player_renderers(board_id: &'static str, next_id: &'static str, garbage_id: &'static str, theme: &Theme) -> (PlayerRenderer, PlayerRenderer, PlayerRenderer) {
+    (
+        IncrementalRenderer::new(CanvasRenderer::new(get_context(board_id), draw::ZOOM).with_theme(theme.clone())),
+        IncrementalRenderer::new(CanvasRenderer::new(get_context(next_id), 15).with_theme(theme.clone())),
+        IncrementalRenderer::new(CanvasRenderer::new(get_context(garbage_id), draw::ZOOM).with_theme(theme.clone())),
+    )
 }
 
 fn get_context(id: &'static str) -> CanvasRenderingContext2d {
@@ -0,0 +1,142 @@
+mod dig;
+mod marathon;
+mod sprint;
+mod survival;
+mod ultra;
+mod zen;
+
+pub use dig::Dig;
+pub use marathon::Marathon;
+pub use sprint::Sprint;
+pub use survival::Survival;
+pub use ultra::Ultra;
+pub use zen::Zen;
+
+use super::attack::Clear;
+use super::game::Delays;
+use super::game::Game;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+pub type Report = Vec<(&'static str, String)>;
+
+// Rules layered on top of a `Game`. Every hook receives the game it is
+// attached to and may end it with `Game::finish`.
+pub trait Mode: ModeClone {
+    fn name(&self) -> &'static str;
+
+    fn start(&mut self, _game: &mut Game) {}
+
+    fn tick(&mut self, _game: &mut Game) {}
+
+    fn lock(&mut self, _game: &mut Game, _clear: &Clear) {}
+
+    // Called when the stack tops out, returns whether the game is over.
+    fn top_out(&mut self, _game: &mut Game) -> bool {
+        true
+    }
+
+    // Ranked modes must not let placements be taken back.
+    fn allows_undo(&self) -> bool {
+        false
+    }
+
+    // Time between a lock and the next piece, see `Delays`.
+    fn delays(&self) -> Delays {
+        Delays::default()
+    }
+
+    // The mode state kept by saved games.
+    fn save(&self) -> SavedMode;
+
+    fn report(&self, game: &Game) -> Report;
+}
+
+// Lets games holding a boxed mode be cloned, any `Clone` mode gets it.
+pub trait ModeClone {
+    fn clone_box(&self) -> Box<dyn Mode>;
+}
+
+impl<T: 'static + Mode + Clone> ModeClone for T {
+    fn clone_box(&self) -> Box<dyn Mode> {
+        Box::new(self.clone())
+    }
+}
+
+impl Clone for Box<dyn Mode> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum SavedMode {
+    Sprint(Sprint),
+    Ultra(Ultra),
+    Marathon(Marathon),
+    Dig(Dig),
+    Survival(Survival),
+    Zen(Zen),
+}
+
+impl SavedMode {
+    pub fn into_mode(self) -> Box<dyn Mode> {
+        match self {
+            SavedMode::Sprint(mode) => Box::new(mode),
+            SavedMode::Ultra(mode) => Box::new(mode),
+            SavedMode::Marathon(mode) => Box::new(mode),
+            SavedMode::Dig(mode) => Box::new(mode),
+            SavedMode::Survival(mode) => Box::new(mode),
+            SavedMode::Zen(mode) => Box::new(mode),
+        }
+    }
+}
+
+// Lets a game save its boxed mode, see `Mode::save`.
+pub fn serialize<S: Serializer>(mode: &Option<Box<dyn Mode>>, serializer: S) -> Result<S::Ok, S::Error> {
+    mode.as_ref().map(|mode| mode.save()).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Box<dyn Mode>>, D::Error> {
+    Ok(Option::<SavedMode>::deserialize(deserializer)?.map(SavedMode::into_mode))
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn Mode>> {
+    match name {
+        "sprint" => Some(Box::new(Sprint::new(40))),
+        "ultra" => Some(Box::new(Ultra::new(ultra::DEFAULT_TIME_BUDGET_MS))),
+        "marathon" => Some(Box::new(Marathon::new(marathon::DEFAULT_LINE_CAP))),
+        "endless" => Some(Box::new(Marathon::endless())),
+        "dig" => Some(Box::new(Dig::new(dig::DEFAULT_TARGET_LINES, dig::DEFAULT_GARBAGE_HEIGHT, dig::DEFAULT_MESSINESS))),
+        "survival" => Some(Box::new(Survival::new(
+            survival::DEFAULT_INITIAL_INTERVAL_MS,
+            survival::DEFAULT_MINIMUM_INTERVAL_MS,
+            survival::DEFAULT_ACCELERATION,
+            survival::DEFAULT_MESSINESS,
+        ))),
+        "zen" => Some(Box::new(Zen::new(true))),
+        "practice" => Some(Box::new(Zen::new(false))),
+        _ => None,
+    }
+}
+
+pub fn format_time(ms: u64) -> String {
+    format!("{}:{:02}.{:03}", ms / 60_000, ms / 1000 % 60, ms % 1000)
+}
+
+pub fn pieces_per_second(game: &Game) -> f64 {
+    match game.elapsed_ms() {
+        0 => 0.,
+        ms => game.pieces() as f64 * 1000. / ms as f64,
+    }
+}
+
+pub fn attack_per_minute(game: &Game) -> f64 {
+    match game.elapsed_ms() {
+        0 => 0.,
+        ms => game.attack() as f64 * 60_000. / ms as f64,
+    }
+}
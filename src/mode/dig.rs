@@ -0,0 +1,107 @@
+use super::super::attack::Clear;
+use super::super::game::Delays;
+use super::super::game::Game;
+use super::super::garbage::HoleGenerator;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+pub const DEFAULT_TARGET_LINES: u32 = 100;
+pub const DEFAULT_GARBAGE_HEIGHT: u32 = 10;
+pub const DEFAULT_MESSINESS: f64 = 0.5;
+
+// Dig through `target_lines` garbage lines. The board starts with
+// `garbage_height` garbage rows and is topped up back to that height after
+// each piece until all the garbage has been sent.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Dig {
+    target_lines: u32,
+    garbage_height: u32,
+    messiness: f64,
+    holes: Option<HoleGenerator>,
+    sent_lines: u32,
+    // Garbage rows on the board at the last lock, whoever put them there.
+    garbage_rows: u32,
+    #[serde(default)]
+    dug_lines: u32,
+}
+
+impl Dig {
+    pub fn new(target_lines: u32, garbage_height: u32, messiness: f64) -> Self {
+        Dig {
+            target_lines,
+            garbage_height,
+            messiness,
+            holes: None,
+            sent_lines: 0,
+            garbage_rows: 0,
+            dug_lines: 0,
+        }
+    }
+
+    pub fn dug_lines(&self) -> u32 {
+        self.dug_lines
+    }
+
+    fn top_up(&mut self, game: &mut Game) {
+        let missing_rows = self.garbage_height.saturating_sub(self.garbage_rows)
+            .min(self.target_lines - self.sent_lines);
+        if 0 == missing_rows {
+            return;
+        }
+        let width = game.board().width();
+        let messiness = self.messiness;
+        let holes = self.holes.get_or_insert_with(|| HoleGenerator::new(width, messiness));
+        let hole_columns = holes.next_hole_columns(missing_rows as usize);
+        self.sent_lines += missing_rows;
+        self.garbage_rows += missing_rows;
+        game.push_garbage(&hole_columns);
+    }
+}
+
+impl Mode for Dig {
+    fn name(&self) -> &'static str {
+        "Dig"
+    }
+
+    fn start(&mut self, game: &mut Game) {
+        self.top_up(game);
+    }
+
+    // Only garbage rows gone since the last lock are dug, not the ones
+    // never sent.
+    fn lock(&mut self, game: &mut Game, _clear: &Clear) {
+        let garbage_rows = game.board().garbage_row_count() as u32;
+        self.dug_lines += self.garbage_rows.saturating_sub(garbage_rows);
+        self.garbage_rows = garbage_rows;
+        if self.dug_lines >= self.target_lines {
+            game.finish();
+            return;
+        }
+        self.top_up(game);
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::NONE
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Dig(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        vec!(
+            ("Mode", format!("{} {}L", self.name(), self.target_lines)),
+            ("Time", format_time(game.elapsed_ms())),
+            ("Garbage", format!("{}/{}", self.dug_lines(), self.target_lines)),
+            ("Lines", game.lines().to_string()),
+            ("Pieces", game.pieces().to_string()),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
@@ -0,0 +1,84 @@
+use super::super::attack::Clear;
+use super::super::game::Game;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+pub const DEFAULT_LINE_CAP: u32 = 150;
+pub const LINES_PER_LEVEL: u32 = 10;
+
+// Level goes up every `LINES_PER_LEVEL` lines, with guideline gravity. The
+// game is won once `line_cap` lines are cleared; without a cap it only
+// ends on top out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Marathon {
+    line_cap: Option<u32>,
+}
+
+impl Marathon {
+    pub fn new(line_cap: u32) -> Self {
+        Marathon {line_cap: Some(line_cap)}
+    }
+
+    pub fn endless() -> Self {
+        Marathon {line_cap: None}
+    }
+
+    fn level_for(&self, lines: u32) -> u32 {
+        let level = 1 + lines / LINES_PER_LEVEL;
+        match self.line_cap {
+            None => level,
+            Some(line_cap) => level.min((line_cap / LINES_PER_LEVEL).max(1)),
+        }
+    }
+}
+
+impl Mode for Marathon {
+    fn name(&self) -> &'static str {
+        match self.line_cap {
+            None => "Endless Marathon",
+            Some(_) => "Marathon",
+        }
+    }
+
+    fn start(&mut self, game: &mut Game) {
+        game.set_level(self.level_for(0));
+    }
+
+    fn lock(&mut self, game: &mut Game, _clear: &Clear) {
+        if let Some(line_cap) = self.line_cap {
+            if game.lines() >= line_cap {
+                game.finish();
+                return;
+            }
+        }
+        let level = self.level_for(game.lines());
+        if level != game.level() {
+            game.set_level(level);
+        }
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Marathon(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        let lines = match self.line_cap {
+            None => game.lines().to_string(),
+            Some(line_cap) => format!("{}/{}", game.lines().min(line_cap), line_cap),
+        };
+        vec!(
+            ("Mode", self.name().to_string()),
+            ("Level", game.level().to_string()),
+            ("Lines", lines),
+            ("Score", game.score().to_string()),
+            ("Time", format_time(game.elapsed_ms())),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
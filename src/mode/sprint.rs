@@ -0,0 +1,54 @@
+use super::super::attack::Clear;
+use super::super::game::Delays;
+use super::super::game::Game;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+// Clear `target_lines` as fast as possible. Timed to the tick of the lock
+// reaching them, see `Game::elapsed_ms`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sprint {
+    target_lines: u32,
+}
+
+impl Sprint {
+    pub fn new(target_lines: u32) -> Self {
+        Sprint {target_lines}
+    }
+}
+
+impl Mode for Sprint {
+    fn name(&self) -> &'static str {
+        "Sprint"
+    }
+
+    fn lock(&mut self, game: &mut Game, _clear: &Clear) {
+        if game.lines() >= self.target_lines {
+            game.finish();
+        }
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::NONE
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Sprint(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        vec!(
+            ("Mode", format!("{} {}L", self.name(), self.target_lines)),
+            ("Time", format_time(game.elapsed_ms())),
+            ("Lines", format!("{}/{}", game.lines().min(self.target_lines), self.target_lines)),
+            ("Pieces", game.pieces().to_string()),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
@@ -0,0 +1,80 @@
+use super::super::game::Game;
+use super::super::garbage::HoleGenerator;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+pub const DEFAULT_INITIAL_INTERVAL_MS: u64 = 8000;
+pub const DEFAULT_MINIMUM_INTERVAL_MS: u64 = 1000;
+pub const DEFAULT_ACCELERATION: f64 = 0.95;
+pub const DEFAULT_MESSINESS: f64 = 0.3;
+
+// A garbage row rises from the bottom every interval, each interval being
+// `acceleration` times the previous one down to `minimum_interval_ms`. Lasts
+// until top out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Survival {
+    interval_ms: f64,
+    minimum_interval_ms: u64,
+    acceleration: f64,
+    messiness: f64,
+    holes: Option<HoleGenerator>,
+    next_garbage_ms: u64,
+    received_lines: u32,
+}
+
+impl Survival {
+    pub fn new(initial_interval_ms: u64, minimum_interval_ms: u64, acceleration: f64, messiness: f64) -> Self {
+        Survival {
+            interval_ms: initial_interval_ms as f64,
+            minimum_interval_ms,
+            acceleration,
+            messiness,
+            holes: None,
+            next_garbage_ms: initial_interval_ms,
+            received_lines: 0,
+        }
+    }
+}
+
+impl Mode for Survival {
+    fn name(&self) -> &'static str {
+        "Survival"
+    }
+
+    // Rows due while cleared rows are still shown rise once they are gone,
+    // the board shown until then being the one before the clear.
+    fn tick(&mut self, game: &mut Game) {
+        if game.elapsed_ms() < self.next_garbage_ms || game.line_clear().is_some() {
+            return;
+        }
+        let width = game.board().width();
+        let messiness = self.messiness;
+        let holes = self.holes.get_or_insert_with(|| HoleGenerator::new(width, messiness));
+        let hole_column = holes.next_hole_column();
+        self.received_lines += 1;
+        self.interval_ms = (self.interval_ms * self.acceleration).max(self.minimum_interval_ms as f64);
+        self.next_garbage_ms += self.interval_ms as u64;
+        game.push_garbage(&[hole_column]);
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Survival(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        vec!(
+            ("Mode", self.name().to_string()),
+            ("Time", format_time(game.elapsed_ms())),
+            ("Garbage", self.received_lines.to_string()),
+            ("Lines", game.lines().to_string()),
+            ("Pieces", game.pieces().to_string()),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
@@ -0,0 +1,58 @@
+use super::super::game::Delays;
+use super::super::game::Game;
+use super::attack_per_minute;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+pub const DEFAULT_TIME_BUDGET_MS: u64 = 2 * 60 * 1000;
+
+// Score as much as possible before the clock runs out.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ultra {
+    time_budget_ms: u64,
+}
+
+impl Ultra {
+    pub fn new(time_budget_ms: u64) -> Self {
+        Ultra {time_budget_ms}
+    }
+}
+
+impl Mode for Ultra {
+    fn name(&self) -> &'static str {
+        "Ultra"
+    }
+
+    fn tick(&mut self, game: &mut Game) {
+        if game.elapsed_ms() >= self.time_budget_ms {
+            game.finish();
+        }
+    }
+
+    fn delays(&self) -> Delays {
+        Delays::NONE
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Ultra(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        vec!(
+            ("Mode", format!("{} {}", self.name(), format_time(self.time_budget_ms))),
+            ("Time", format_time(game.elapsed_ms().min(self.time_budget_ms))),
+            ("Score", game.score().to_string()),
+            ("Lines", game.lines().to_string()),
+            ("Attack", game.attack().to_string()),
+            ("APM", format!("{:.2}", attack_per_minute(game))),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
+
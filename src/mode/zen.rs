@@ -0,0 +1,60 @@
+use super::super::game::Game;
+use super::format_time;
+use super::pieces_per_second;
+use super::Mode;
+use super::Report;
+use super::SavedMode;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+// Practice without pressure: topping out only empties the board, gravity
+// may be turned off and placements can be undone.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Zen {
+    gravity: bool,
+    board_clears: u32,
+}
+
+impl Zen {
+    pub fn new(gravity: bool) -> Self {
+        Zen {gravity, board_clears: 0}
+    }
+}
+
+impl Mode for Zen {
+    fn name(&self) -> &'static str {
+        "Zen"
+    }
+
+    fn start(&mut self, game: &mut Game) {
+        if !self.gravity {
+            game.set_gravity(0.);
+        }
+    }
+
+    fn top_out(&mut self, game: &mut Game) -> bool {
+        self.board_clears += 1;
+        game.clear_board();
+        false
+    }
+
+    fn allows_undo(&self) -> bool {
+        true
+    }
+
+    fn save(&self) -> SavedMode {
+        SavedMode::Zen(self.clone())
+    }
+
+    fn report(&self, game: &Game) -> Report {
+        vec!(
+            ("Mode", self.name().to_string()),
+            ("Time", format_time(game.elapsed_ms())),
+            ("Lines", game.lines().to_string()),
+            ("Pieces", game.pieces().to_string()),
+            ("Top outs", self.board_clears.to_string()),
+            ("PPS", format!("{:.2}", pieces_per_second(game))),
+        )
+    }
+}
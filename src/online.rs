@@ -0,0 +1,189 @@
+use super::attack::Guideline;
+use super::game::State;
+use super::input::Input;
+use super::protocol::Packet;
+use super::rollback;
+use super::rollback::Rollback;
+use super::rollback::Transport;
+use super::utils::log;
+use super::versus;
+use super::versus::Versus;
+
+use js_sys::Uint8Array;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::BinaryType;
+use web_sys::MessageEvent;
+use web_sys::WebSocket;
+
+const INPUT_DELAY: u32 = 2;
+const MAX_PREDICTION: u32 = 8;
+
+// Input frames go through rollback, every other packet is kept aside for
+// the match itself.
+pub struct WebSocketTransport {
+    socket: WebSocket,
+    incoming: Rc<RefCell<VecDeque<Packet>>>,
+    control: VecDeque<Packet>,
+}
+
+impl WebSocketTransport {
+    // `Join` is sent as soon as the connection opens.
+    pub fn connect(url: &str, join: Packet) -> Result<Self, JsValue> {
+        let socket = WebSocket::new(url)?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let incoming = Rc::new(RefCell::new(VecDeque::new()));
+        let onmessage = {
+            let incoming = Rc::clone(&incoming);
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                let bytes = Uint8Array::new(&event.data()).to_vec();
+                match Packet::decode(&bytes) {
+                    Ok(packet) => incoming.borrow_mut().push_back(packet),
+                    Err(error) => log(&format!("dropped packet: {}", error)),
+                }
+            }) as Box<dyn FnMut(_)>)
+        };
+        socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        onmessage.forget();
+
+        let onopen = {
+            let socket = socket.clone();
+            Closure::wrap(Box::new(move || {
+                if socket.send_with_u8_array(&join.encode()).is_err() {
+                    log("could not join");
+                }
+            }) as Box<dyn FnMut()>)
+        };
+        socket.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        onopen.forget();
+
+        Ok(WebSocketTransport {socket, incoming, control: VecDeque::new()})
+    }
+
+    pub fn send_packet(&self, packet: &Packet) {
+        if self.socket.send_with_u8_array(&packet.encode()).is_err() {
+            log("could not send packet");
+        }
+    }
+
+    pub fn take_control_packets(&mut self) -> Vec<Packet> {
+        self.sort_incoming();
+        self.control.drain(..).collect()
+    }
+
+    fn sort_incoming(&mut self) -> Vec<rollback::Message> {
+        let mut messages = Vec::new();
+        for packet in self.incoming.borrow_mut().drain(..) {
+            match packet {
+                Packet::Inputs {..} => messages.extend(packet.into_rollback()),
+                packet => self.control.push_back(packet),
+            }
+        }
+        messages
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn send(&mut self, message: rollback::Message) {
+        self.send_packet(&Packet::from_rollback(message));
+    }
+
+    fn receive(&mut self) -> Vec<rollback::Message> {
+        self.sort_incoming()
+    }
+}
+
+enum Stage {
+    Lobby(WebSocketTransport, Option<u8>),
+    // Whether the local player's knock out was sent.
+    Playing(Box<Rollback<Versus, WebSocketTransport>>, u8, bool),
+    // The match as it ended, and its winner.
+    Over(Box<Versus>, u8, u8),
+}
+
+// Versus against a player on another machine, through the match server.
+pub struct Online {
+    // Only empty while moving from one stage to the next.
+    stage: Option<Stage>,
+}
+
+impl Online {
+    pub fn connect(url: &str, room: &str, name: &str) -> Result<Self, JsValue> {
+        let join = Packet::Join {room: room.to_string(), name: name.to_string()};
+        let transport = WebSocketTransport::connect(url, join)?;
+        Ok(Online {stage: Some(Stage::Lobby(transport, None))})
+    }
+
+    pub fn local_player(&self) -> Option<usize> {
+        match &self.stage {
+            Some(Stage::Playing(_, player, _)) | Some(Stage::Over(_, player, _)) => Some(*player as usize),
+            _ => None,
+        }
+    }
+
+    pub fn versus(&self) -> Option<&Versus> {
+        match &self.stage {
+            Some(Stage::Playing(rollback, _, _)) => Some(rollback.state()),
+            Some(Stage::Over(versus, _, _)) => Some(versus),
+            _ => None,
+        }
+    }
+
+    // Once the server decided the match.
+    pub fn winner(&self) -> Option<usize> {
+        match &self.stage {
+            Some(Stage::Over(_, _, winner)) => Some(*winner as usize),
+            _ => None,
+        }
+    }
+
+    pub fn tick(&mut self, input: Input) {
+        self.stage = self.stage.take().map(|stage| match stage {
+            Stage::Lobby(transport, player) => Self::wait(transport, player),
+            Stage::Playing(rollback, player, knocked_out) => Self::play(rollback, player, knocked_out, input),
+            Stage::Over(versus, player, winner) => Stage::Over(versus, player, winner),
+        });
+    }
+
+    fn wait(mut transport: WebSocketTransport, mut player: Option<u8>) -> Stage {
+        for packet in transport.take_control_packets() {
+            match (packet, player) {
+                (Packet::Joined {player: joined}, _) => {
+                    log(&format!("joined as player {}", joined));
+                    player = Some(joined);
+                    transport.send_packet(&Packet::Ready);
+                },
+                (Packet::Start {seed}, Some(player)) => {
+                    let mut versus = Versus::with_seed(Box::new(Guideline), versus::DEFAULT_GARBAGE_DELAY_TICKS, seed);
+                    versus.run();
+                    let rollback = Rollback::new(versus, transport, player as usize, INPUT_DELAY, MAX_PREDICTION);
+                    return Stage::Playing(Box::new(rollback), player, false);
+                },
+                _ => {},
+            }
+        }
+        Stage::Lobby(transport, player)
+    }
+
+    // The knock out is only sent once no rollback can undo it, the
+    // predicted game possibly ending where the confirmed one does not.
+    fn play(mut rollback: Box<Rollback<Versus, WebSocketTransport>>, player: u8, mut knocked_out: bool, input: Input) -> Stage {
+        rollback.add_local_input(input);
+        rollback.advance();
+        if !knocked_out && State::Over == rollback.confirmed_state().game(player as usize).state() {
+            rollback.transport_mut().send_packet(&Packet::KnockOut {player});
+            knocked_out = true;
+        }
+        for packet in rollback.transport_mut().take_control_packets() {
+            if let Packet::Results {winner} = packet {
+                log(&format!("player {} wins", winner));
+                return Stage::Over(Box::new(rollback.state().clone()), player, winner);
+            }
+        }
+        Stage::Playing(rollback, player, knocked_out)
+    }
+}
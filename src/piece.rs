@@ -1,6 +1,9 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::convert::TryFrom;
 use std::ops::Add;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum Type {
     I,
     T,
@@ -9,11 +12,42 @@ pub enum Type {
     J,
     S,
     Z,
+    // Not a piece: filler cells of the garbage rows.
+    Garbage,
+}
+
+impl Type {
+    pub fn letter(self) -> char {
+        match self {
+            Type::I => 'I',
+            Type::T => 'T',
+            Type::O => 'O',
+            Type::L => 'L',
+            Type::J => 'J',
+            Type::S => 'S',
+            Type::Z => 'Z',
+            Type::Garbage => 'G',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'I' => Some(Type::I),
+            'T' => Some(Type::T),
+            'O' => Some(Type::O),
+            'L' => Some(Type::L),
+            'J' => Some(Type::J),
+            'S' => Some(Type::S),
+            'Z' => Some(Type::Z),
+            'G' => Some(Type::Garbage),
+            _ => None,
+        }
+    }
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy)]
-enum Orientation {
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub enum Orientation {
     Top = 0,
     Right = 1,
     Bottom = 2,
@@ -37,7 +71,7 @@ impl Add<i8> for Orientation {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Cell(pub Option<Type>);
 
 pub type Shape = Vec<Vec<Cell>>;
@@ -84,6 +118,7 @@ pub fn index(shape_index: usize, offset: i32) -> Option<usize> {
     }
 }
 
+#[derive(Clone)]
 struct PieceTemplate {
     top_shape: Shape,
     right_shape: Shape,
@@ -120,6 +155,7 @@ impl PieceTemplate {
             Type::J => Self::j_top_shape(),
             Type::S => Self::s_top_shape(),
             Type::Z => Self::z_top_shape(),
+            Type::Garbage => panic!("garbage has no shape"),
         }
     }
 
@@ -193,6 +229,8 @@ impl PieceTemplate {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "PlacedPiece", try_from = "PlacedPiece")]
 pub struct Piece {
     template: PieceTemplate,
     row_offset: i32,
@@ -219,6 +257,9 @@ impl Piece {
     pub fn piece_type(&self) -> Type {
         self.piece_type
     }
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
     pub fn rotate_clockwise(&mut self) {
         self.orientation = self.orientation + 1;
     }
@@ -228,6 +269,9 @@ impl Piece {
     pub fn move_down(&mut self) {
         self.row_offset -= 1;
     }
+    pub fn move_up(&mut self, rows: i32) {
+        self.row_offset += rows;
+    }
     pub fn move_left(&mut self) {
         self.column_offset -= 1;
     }
@@ -253,3 +297,36 @@ impl Piece {
     }
 
 }
+
+// What is saved of a piece, the shapes being derived from its type.
+#[derive(Serialize, Deserialize)]
+struct PlacedPiece {
+    piece_type: Type,
+    orientation: Orientation,
+    row_offset: i32,
+    column_offset: i32,
+}
+
+impl From<Piece> for PlacedPiece {
+    fn from(piece: Piece) -> Self {
+        PlacedPiece {
+            piece_type: piece.piece_type,
+            orientation: piece.orientation,
+            row_offset: piece.row_offset,
+            column_offset: piece.column_offset,
+        }
+    }
+}
+
+impl TryFrom<PlacedPiece> for Piece {
+    type Error = &'static str;
+
+    fn try_from(placed: PlacedPiece) -> Result<Self, Self::Error> {
+        if let Type::Garbage = placed.piece_type {
+            return Err("garbage is not a piece");
+        }
+        let mut piece = Piece::new(placed.row_offset, placed.column_offset, placed.piece_type);
+        piece.orientation = placed.orientation;
+        Ok(piece)
+    }
+}
@@ -1,16 +1,71 @@
 use super::piece;
 
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
+use rand::Rng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use serde::Serialize;
+use std::convert::TryFrom;
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "GeneratorState", try_from = "GeneratorState")]
 pub struct PieceTypeGenerator {
     bag: Vec<piece::Type>,
+    rng: StdRng,
+    seed: u64,
+    bags: u32,
+}
+
+// Bags are drawn again one by one on load, which saves dealing more than
+// this are not trusted with: it is days of play.
+pub const MAX_BAGS: u32 = 1_000_000;
+
+// The random generator state is not saved as is: it is seeded again and
+// the bags dealt so far are drawn once more.
+#[derive(Serialize, Deserialize)]
+struct GeneratorState {
+    seed: u64,
+    bags: u32,
+    bag: Vec<piece::Type>,
+}
+
+impl From<PieceTypeGenerator> for GeneratorState {
+    fn from(generator: PieceTypeGenerator) -> Self {
+        GeneratorState {seed: generator.seed, bags: generator.bags, bag: generator.bag}
+    }
+}
+
+impl TryFrom<GeneratorState> for PieceTypeGenerator {
+    type Error = String;
+
+    fn try_from(state: GeneratorState) -> Result<Self, Self::Error> {
+        if state.bags > MAX_BAGS {
+            return Err(format!("{} bags dealt, more than {}", state.bags, MAX_BAGS));
+        }
+        let mut generator = PieceTypeGenerator::with_seed(state.seed);
+        for _ in 0..state.bags {
+            create_next_random_bag(&mut generator.rng);
+        }
+        generator.bags = state.bags;
+        generator.bag = state.bag;
+        Ok(generator)
+    }
 }
 
 impl PieceTypeGenerator {
     pub fn new() -> Self {
+        Self::with_seed(thread_rng().gen())
+    }
+
+    // The same seed always deals the same pieces.
+    pub fn with_seed(seed: u64) -> Self {
         PieceTypeGenerator {
             bag: Vec::with_capacity(7),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
+            bags: 0,
         }
     }
 
@@ -18,15 +73,15 @@ impl PieceTypeGenerator {
         match self.bag.pop() {
             Some(piece_type) => piece_type,
             None => {
-                self.bag = create_next_random_bag();
+                self.bag = create_next_random_bag(&mut self.rng);
+                self.bags += 1;
                 self.bag.pop().unwrap()
             },
         }
     }
 }
 
-fn create_next_random_bag() -> Vec<piece::Type> {
-    let mut rng = thread_rng();
+fn create_next_random_bag(rng: &mut StdRng) -> Vec<piece::Type> {
     let mut bag = vec!(
         piece::Type::I,
         piece::Type::T,
@@ -36,6 +91,6 @@ fn create_next_random_bag() -> Vec<piece::Type> {
         piece::Type::S,
         piece::Type::Z,
     );
-    bag.shuffle(&mut rng);
+    bag.shuffle(rng);
     bag
 }
@@ -0,0 +1,201 @@
+use super::input::Input;
+use super::rollback;
+
+use std::fmt;
+
+// Messages exchanged between the players and the match server. Clients
+// send `Join`, `Ready`, `Inputs` and `KnockOut`, the server answers with
+// `Joined`, `Start` and `Results` and relays the rest with the player it
+// comes from. Garbage needs no message of its own: both players simulate
+// both games from the inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Packet {
+    Join { room: String, name: String },
+    Joined { player: u8 },
+    Ready,
+    Start { seed: u64 },
+    Inputs { player: u8, start_frame: u32, inputs: Vec<Input>, ack: u32 },
+    KnockOut { player: u8 },
+    Results { winner: u8 },
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DecodeError {
+    UnexpectedEnd,
+    UnknownTag(u8),
+    InvalidText,
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => write!(formatter, "packet ends too early"),
+            DecodeError::UnknownTag(tag) => write!(formatter, "unknown packet tag {}", tag),
+            DecodeError::InvalidText => write!(formatter, "text is not UTF-8"),
+            DecodeError::TrailingBytes => write!(formatter, "bytes left after the packet"),
+        }
+    }
+}
+
+const JOIN: u8 = 0;
+const JOINED: u8 = 1;
+const READY: u8 = 2;
+const START: u8 = 3;
+const INPUTS: u8 = 4;
+const KNOCK_OUT: u8 = 5;
+const RESULTS: u8 = 6;
+
+impl Packet {
+    pub fn from_rollback(message: rollback::Message) -> Self {
+        Packet::Inputs {
+            player: message.player as u8,
+            start_frame: message.start_frame,
+            inputs: message.inputs,
+            ack: message.ack,
+        }
+    }
+
+    pub fn into_rollback(self) -> Option<rollback::Message> {
+        match self {
+            Packet::Inputs {player, start_frame, inputs, ack} => Some(rollback::Message {
+                player: player as usize,
+                start_frame,
+                inputs,
+                ack,
+            }),
+            _ => None,
+        }
+    }
+
+    // One tag byte, then the fields: integers as LEB128 varints, text
+    // length prefixed and inputs packed two per byte.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Packet::Join {room, name} => {
+                bytes.push(JOIN);
+                write_text(&mut bytes, room);
+                write_text(&mut bytes, name);
+            },
+            Packet::Joined {player} => {
+                bytes.push(JOINED);
+                bytes.push(*player);
+            },
+            Packet::Ready => bytes.push(READY),
+            Packet::Start {seed} => {
+                bytes.push(START);
+                write_varint(&mut bytes, *seed);
+            },
+            Packet::Inputs {player, start_frame, inputs, ack} => {
+                bytes.push(INPUTS);
+                bytes.push(*player);
+                write_varint(&mut bytes, *start_frame as u64);
+                write_varint(&mut bytes, *ack as u64);
+                write_varint(&mut bytes, inputs.len() as u64);
+                for pair in inputs.chunks(2) {
+                    let high = pair.get(1).map_or(0, |input| input.bits());
+                    bytes.push((pair[0].bits() & 0x0F) | (high << 4));
+                }
+            },
+            Packet::KnockOut {player} => {
+                bytes.push(KNOCK_OUT);
+                bytes.push(*player);
+            },
+            Packet::Results {winner} => {
+                bytes.push(RESULTS);
+                bytes.push(*winner);
+            },
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader {bytes, position: 0};
+        let packet = match reader.byte()? {
+            JOIN => Packet::Join {room: reader.text()?, name: reader.text()?},
+            JOINED => Packet::Joined {player: reader.byte()?},
+            READY => Packet::Ready,
+            START => Packet::Start {seed: reader.varint()?},
+            INPUTS => {
+                let player = reader.byte()?;
+                let start_frame = reader.varint()? as u32;
+                let ack = reader.varint()? as u32;
+                let count = reader.varint()?;
+                // Two inputs per byte: a count the packet cannot hold is
+                // rejected before anything is allocated for it.
+                if count > 2 * reader.remaining() as u64 {
+                    return Err(DecodeError::UnexpectedEnd);
+                }
+                let count = count as usize;
+                let mut inputs = Vec::with_capacity(count);
+                while inputs.len() < count {
+                    let byte = reader.byte()?;
+                    inputs.push(Input::from_bits(byte & 0x0F));
+                    if inputs.len() < count {
+                        inputs.push(Input::from_bits(byte >> 4));
+                    }
+                }
+                Packet::Inputs {player, start_frame, inputs, ack}
+            },
+            KNOCK_OUT => Packet::KnockOut {player: reader.byte()?},
+            RESULTS => Packet::Results {winner: reader.byte()?},
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        };
+        if reader.position != bytes.len() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(packet)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+    write_varint(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.bytes.get(self.position).ok_or(DecodeError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte < 0x80 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    fn text(&mut self) -> Result<String, DecodeError> {
+        let length = self.varint()? as usize;
+        let end = self.position.checked_add(length).filter(|&end| end <= self.bytes.len())
+            .ok_or(DecodeError::UnexpectedEnd)?;
+        let text = std::str::from_utf8(&self.bytes[self.position..end]).map_err(|_| DecodeError::InvalidText)?;
+        self.position = end;
+        Ok(text.to_string())
+    }
+}
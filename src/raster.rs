@@ -0,0 +1,306 @@
+//! Pixel images of boards, as PNG screenshots and animated GIF replays.
+
+use super::board::Board;
+use super::draw;
+use super::draw::Renderer;
+use super::game::Game;
+use super::game_loop::TICKS_PER_SECOND;
+use super::piece::Piece;
+use super::replay::Replay;
+use super::theme;
+use super::theme::CellStyle;
+use super::theme::Theme;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+// Pixels are `[red, green, blue]`, row after row from the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, color: [u8; 3]) -> Self {
+        Image {width, height, pixels: vec![color; width * height]}
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        self.pixels[y * self.width + x]
+    }
+
+    // Pixels out of the image are left out.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 3]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + column] = color;
+            }
+        }
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().expect("PNG header written to memory");
+        let data: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+        writer.write_image_data(&data).expect("PNG data written to memory");
+        drop(writer);
+        png
+    }
+
+    // Frame of an animated GIF, with the palette of its own colors.
+    fn to_gif_frame(&self, delay: u16) -> gif::Frame<'static> {
+        let width = self.width.try_into().expect("GIF at most 65535 pixels wide");
+        let height = self.height.try_into().expect("GIF at most 65535 pixels high");
+        let mut indices = HashMap::new();
+        let mut palette = Vec::new();
+        for pixel in self.pixels.iter() {
+            if !indices.contains_key(pixel) {
+                indices.insert(*pixel, indices.len());
+                palette.extend_from_slice(pixel);
+            }
+        }
+        let mut frame = if indices.len() <= 256 {
+            let pixels: Vec<u8> = self.pixels.iter().map(|pixel| indices[pixel] as u8).collect();
+            gif::Frame::from_palette_pixels(width, height, pixels, palette, None)
+        } else {
+            let pixels: Vec<u8> = self.pixels.iter().flatten().copied().collect();
+            gif::Frame::from_rgb(width, height, &pixels)
+        };
+        frame.delay = delay;
+        frame
+    }
+}
+
+// Draws into an `Image` the pixels `draw::CanvasRenderer` would draw on a
+// canvas.
+pub struct RasterRenderer {
+    zoom: usize,
+    rows: usize,
+    columns: usize,
+    image: Image,
+    theme: Theme,
+}
+
+impl RasterRenderer {
+    pub fn new(zoom: usize) -> Self {
+        RasterRenderer {zoom, rows: 0, columns: 0, image: Image::new(0, 0, WHITE), theme: Theme::classic()}
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    fn y(&self, row: usize) -> usize {
+        draw::line_y(self.rows, row, self.zoom)
+    }
+
+    fn x(&self, column: usize) -> usize {
+        draw::line_x(column, self.zoom)
+    }
+}
+
+impl Renderer for RasterRenderer {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.rows = rows;
+        self.columns = columns;
+        self.image = Image::new(self.x(columns) + 1, self.y(0) + 1, WHITE);
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let (x, y) = (self.x(column) + 1, self.y(row + 1) + 1);
+        self.image.fill_rect(x, y, self.zoom, self.zoom, rgb(color));
+    }
+
+    fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    fn background(&mut self, color: &str) {
+        let (width, height) = (self.image.width, self.image.height);
+        self.image.fill_rect(0, 0, width, height, rgb(color));
+    }
+
+    fn styled_cell(&mut self, row: usize, column: usize, color: &str, style: CellStyle) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let (x, y, size) = (self.x(column) + 1, self.y(row + 1) + 1, self.zoom);
+        let edge = draw::edge_width(self.zoom).min(size);
+        let color = rgb(color);
+        match style {
+            CellStyle::Flat => self.image.fill_rect(x, y, size, size, color),
+            CellStyle::Beveled => {
+                self.image.fill_rect(x, y, size, size, color);
+                let light = mix(color, WHITE, 0.45);
+                let dark = mix(color, [0x00, 0x00, 0x00], 0.35);
+                self.image.fill_rect(x, y, size, edge, light);
+                self.image.fill_rect(x, y + edge, edge, size - edge, light);
+                self.image.fill_rect(x + edge, y + size - edge, size - edge, edge, dark);
+                self.image.fill_rect(x + size - edge, y + edge, edge, size - 2 * edge, dark);
+            },
+            CellStyle::Outlined => {
+                self.image.fill_rect(x, y, size, edge, color);
+                self.image.fill_rect(x, y + size - edge, size, edge, color);
+                self.image.fill_rect(x, y, edge, size, color);
+                self.image.fill_rect(x + size - edge, y, edge, size, color);
+            },
+        }
+    }
+
+    // Letters of a five by seven font, blown up to about half a cell.
+    fn glyph(&mut self, row: usize, column: usize, glyph: char, color: &str) {
+        let rows = match glyph_rows(glyph) {
+            Some(rows) => rows,
+            None => return,
+        };
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let scale = (self.zoom / 10).max(1);
+        let x = self.x(column) + 1 + self.zoom.saturating_sub(5 * scale) / 2;
+        let y = self.y(row + 1) + 1 + self.zoom.saturating_sub(7 * scale) / 2;
+        let color = rgb(color);
+        for (glyph_row, bits) in rows.iter().enumerate() {
+            for glyph_column in 0..5 {
+                if 0 != bits & (0b10000 >> glyph_column) {
+                    self.image.fill_rect(x + glyph_column * scale, y + glyph_row * scale, scale, scale, color);
+                }
+            }
+        }
+    }
+
+    fn grid(&mut self, color: &str) {
+        let color = rgb(color);
+        let (width, height) = (self.image.width, self.image.height);
+        for row in 0..=self.rows {
+            self.image.fill_rect(0, self.y(row), width, 1, color);
+        }
+        for column in 0..=self.columns {
+            self.image.fill_rect(self.x(column), 0, 1, height, color);
+        }
+    }
+
+    fn horizontal_line(&mut self, row: usize, color: &str) {
+        let width = self.image.width;
+        self.image.fill_rect(0, self.y(row), width, 1, rgb(color));
+    }
+
+    // Without any font to draw with, images leave texts out.
+    fn text(&mut self, _row: usize, _text: &str, _color: &str) {}
+}
+
+const WHITE: [u8; 3] = [0xFF, 0xFF, 0xFF];
+
+// Rows of the glyphs of the pieces, top first, the leftmost pixel in the
+// highest of five bits.
+fn glyph_rows(glyph: char) -> Option<[u8; 7]> {
+    match glyph {
+        'I' => Some([0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+        'T' => Some([0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+        'O' => Some([0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+        'L' => Some([0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+        'J' => Some([0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+        'S' => Some([0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+        'Z' => Some([0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+        _ => None,
+    }
+}
+
+// `color` moved `amount` of the way to `other`, like a translucent `other`
+// drawn over it.
+fn mix(color: [u8; 3], other: [u8; 3], amount: f64) -> [u8; 3] {
+    let mut mixed = color;
+    for (channel, other) in mixed.iter_mut().zip(other.iter()) {
+        *channel = (*channel as f64 + (*other as f64 - *channel as f64) * amount).round() as u8;
+    }
+    mixed
+}
+
+// Themes are checked to only have colors `theme::rgb` knows, see
+// `Theme::from_json`.
+fn rgb(color: &str) -> [u8; 3] {
+    theme::rgb(color).unwrap_or([0x00, 0x00, 0x00])
+}
+
+// `board` as an image, see `draw::draw_position_with_ghost`.
+pub fn board_to_image(board: &Board, piece: Option<&Piece>, ghost: bool, theme: &Theme) -> Image {
+    let mut renderer = RasterRenderer::new(draw::ZOOM).with_theme(theme.clone());
+    draw::draw_position_with_ghost(board, piece, ghost, &mut renderer);
+    renderer.image
+}
+
+pub fn board_to_png(board: &Board, piece: Option<&Piece>, ghost: bool, theme: &Theme) -> Vec<u8> {
+    board_to_image(board, piece, ghost, theme).to_png()
+}
+
+// The board as the player sees it, ghost included.
+pub fn game_to_png(game: &Game, theme: &Theme) -> Vec<u8> {
+    board_to_png(game.board(), game.piece(), true, theme)
+}
+
+// Plays `replay` back as an animated GIF looping forever, showing every
+// `frames_per_image`th frame and the last one, each for as long as it lasts.
+// Images are encoded as soon as the next one tells how long they last, only
+// one of them being held at a time whatever the length of the replay.
+pub fn replay_to_gif(replay: &Replay, frames_per_image: usize, theme: &Theme) -> Vec<u8> {
+    let frames_per_image = frames_per_image.max(1);
+    let last_frame = replay.frames();
+    let mut encoder: Option<gif::Encoder<Vec<u8>>> = None;
+    // Image not encoded yet, and the frame it shows.
+    let mut pending: Option<(usize, Image)> = None;
+    let mut frame = 0;
+    replay.play(|game| {
+        if 0 == frame % frames_per_image || last_frame == frame {
+            let image = board_to_image(game.board(), game.piece(), true, theme);
+            let encoder = encoder.get_or_insert_with(|| new_gif_encoder(&image));
+            if let Some((pending_frame, pending_image)) = pending.take() {
+                write_gif_frame(encoder, &pending_image, frame - pending_frame);
+            }
+            pending = Some((frame, image));
+        }
+        frame += 1;
+    });
+
+    let mut encoder = match encoder {
+        Some(encoder) => encoder,
+        None => return Vec::new(),
+    };
+    if let Some((_, image)) = pending {
+        write_gif_frame(&mut encoder, &image, frames_per_image);
+    }
+    encoder.into_inner().expect("GIF written to memory")
+}
+
+fn new_gif_encoder(image: &Image) -> gif::Encoder<Vec<u8>> {
+    let (width, height) = (image.width as u16, image.height as u16);
+    let mut encoder = gif::Encoder::new(Vec::new(), width, height, &[]).expect("GIF header written to memory");
+    encoder.set_repeat(gif::Repeat::Infinite).expect("GIF written to memory");
+    encoder
+}
+
+// Shown for as long as `frames` last.
+fn write_gif_frame(encoder: &mut gif::Encoder<Vec<u8>>, image: &Image, frames: usize) {
+    let centiseconds = (frames * 100 / TICKS_PER_SECOND as usize).max(1) as u16;
+    encoder.write_frame(&image.to_gif_frame(centiseconds)).expect("GIF frame written to memory");
+}
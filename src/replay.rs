@@ -0,0 +1,38 @@
+use super::game::Game;
+use super::input;
+use super::input::Input;
+
+// A single player game recorded as where it started from and what was
+// pressed on each frame since, enough to play it again exactly.
+#[derive(Clone)]
+pub struct Replay {
+    start: Game,
+    inputs: Vec<Input>,
+}
+
+impl Replay {
+    pub fn new(start: Game) -> Self {
+        Replay {start, inputs: Vec::new()}
+    }
+
+    // Inputs of the next frame, to be recorded only for frames the game
+    // actually ran: paused ones would otherwise be played.
+    pub fn record(&mut self, input: Input) {
+        self.inputs.push(input);
+    }
+
+    pub fn frames(&self) -> usize {
+        self.inputs.len()
+    }
+
+    // Calls `on_frame` with the game at the start, then after each frame.
+    pub fn play(&self, mut on_frame: impl FnMut(&Game)) {
+        let mut game = self.start.clone();
+        on_frame(&game);
+        for &frame_input in self.inputs.iter() {
+            input::apply(&mut game, frame_input);
+            game.tick();
+            on_frame(&game);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use super::input::Input;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+// Anything that moves forward one frame at a time from the inputs of all
+// the players, always ending up in the same state from the same inputs.
+pub trait Simulation: Clone {
+    fn advance(&mut self, inputs: &[Input]);
+}
+
+// Inputs of `player` for the frames starting at `start_frame`, along with
+// how many frames of the receiver's inputs the sender got so far.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub player: usize,
+    pub start_frame: u32,
+    pub inputs: Vec<Input>,
+    pub ack: u32,
+}
+
+// Delivers messages to the other peer, possibly late, out of order or not
+// at all.
+pub trait Transport {
+    fn send(&mut self, message: Message);
+
+    fn receive(&mut self) -> Vec<Message>;
+}
+
+// Two player rollback: the local player's inputs are applied right away
+// (after `input_delay` frames) while the remote ones are predicted until
+// they arrive. When an input turns out to differ from its prediction, the
+// simulation goes back to that frame and plays again up to the present.
+pub struct Rollback<S: Simulation, T: Transport> {
+    state: S,
+    frame: u32,
+    local_player: usize,
+    input_delay: u32,
+    max_prediction: u32,
+    transport: T,
+    local_inputs: BTreeMap<u32, Input>,
+    remote_inputs: BTreeMap<u32, Input>,
+    // Remote inputs used for each frame not confirmed yet.
+    used_remote_inputs: BTreeMap<u32, Input>,
+    // Frames of remote inputs received without gaps.
+    confirmed_frames: u32,
+    // Frames of local inputs the remote peer acknowledged.
+    acknowledged_frames: u32,
+    // State at the start of each frame not confirmed yet.
+    saved_states: VecDeque<(u32, S)>,
+    rollbacks: u32,
+}
+
+impl<S: Simulation, T: Transport> Rollback<S, T> {
+    pub fn new(state: S, transport: T, local_player: usize, input_delay: u32, max_prediction: u32) -> Self {
+        let local_inputs = (0..input_delay).map(|frame| (frame, Input::NONE)).collect();
+        Rollback {
+            state,
+            frame: 0,
+            local_player,
+            input_delay,
+            max_prediction,
+            transport,
+            local_inputs,
+            remote_inputs: BTreeMap::new(),
+            used_remote_inputs: BTreeMap::new(),
+            confirmed_frames: 0,
+            acknowledged_frames: 0,
+            saved_states: VecDeque::new(),
+            rollbacks: 0,
+        }
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    // State after the frames all the inputs are known for, which no
+    // rollback changes anymore.
+    pub fn confirmed_state(&self) -> &S {
+        // States are kept from the first unconfirmed frame on.
+        self.saved_states.front().map_or(&self.state, |(_, state)| state)
+    }
+
+    pub fn frame(&self) -> u32 {
+        self.frame
+    }
+
+    pub fn confirmed_frames(&self) -> u32 {
+        self.confirmed_frames
+    }
+
+    pub fn rollbacks(&self) -> u32 {
+        self.rollbacks
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    // Records what the local player pressed this frame. It is played
+    // `input_delay` frames later.
+    pub fn add_local_input(&mut self, input: Input) {
+        *self.local_inputs.entry(self.frame + self.input_delay).or_insert(Input::NONE) |= input;
+    }
+
+    // Simulates the next frame. Returns false, without advancing, when the
+    // remote inputs are too far behind to keep predicting.
+    pub fn advance(&mut self) -> bool {
+        self.receive();
+        let can_predict = self.frame < self.confirmed_frames + self.max_prediction;
+        if can_predict {
+            self.simulate_frame();
+        }
+        self.send();
+        can_predict
+    }
+
+    // Exchanges inputs without advancing, e.g. while waiting for the peer.
+    pub fn poll(&mut self) {
+        self.receive();
+        self.send();
+    }
+
+    // Takes in the remote inputs and rolls back if a prediction was wrong.
+    fn receive(&mut self) {
+        let mut rollback_frame: Option<u32> = None;
+        for message in self.transport.receive() {
+            if message.player == self.local_player {
+                continue;
+            }
+            self.acknowledged_frames = self.acknowledged_frames.max(message.ack);
+            for (index, &input) in message.inputs.iter().enumerate() {
+                let frame = message.start_frame + index as u32;
+                if frame < self.confirmed_frames || self.remote_inputs.contains_key(&frame) {
+                    continue;
+                }
+                self.remote_inputs.insert(frame, input);
+                if let Some(&used_input) = self.used_remote_inputs.get(&frame) {
+                    if used_input != input {
+                        rollback_frame = Some(rollback_frame.map_or(frame, |rollback_frame| rollback_frame.min(frame)));
+                    }
+                }
+            }
+        }
+        if let Some(rollback_frame) = rollback_frame {
+            self.rollback(rollback_frame);
+        }
+        while self.remote_inputs.contains_key(&self.confirmed_frames) {
+            self.confirmed_frames += 1;
+        }
+        self.forget_confirmed();
+    }
+
+    fn rollback(&mut self, frame: u32) {
+        let present = self.frame;
+        while let Some((saved_frame, state)) = self.saved_states.pop_back() {
+            if saved_frame == frame {
+                self.state = state;
+                break;
+            }
+        }
+        self.frame = frame;
+        self.rollbacks += 1;
+        while self.frame < present {
+            self.simulate_frame();
+        }
+    }
+
+    fn simulate_frame(&mut self) {
+        let frame = self.frame;
+        let local_input = *self.local_inputs.entry(frame).or_insert(Input::NONE);
+        // Nothing pressed is the likeliest for single frame actions.
+        let remote_input = self.remote_inputs.get(&frame).copied().unwrap_or(Input::NONE);
+        self.used_remote_inputs.insert(frame, remote_input);
+        let mut inputs = [Input::NONE; 2];
+        inputs[self.local_player] = local_input;
+        inputs[1 - self.local_player] = remote_input;
+        self.saved_states.push_back((frame, self.state.clone()));
+        self.state.advance(&inputs);
+        self.frame += 1;
+    }
+
+    // Frames before the first unconfirmed one can not be rolled back to.
+    // Local inputs are kept until acknowledged as well, to be sent again.
+    fn forget_confirmed(&mut self) {
+        let confirmed_frame = self.confirmed_frames.min(self.frame);
+        while let Some((saved_frame, _)) = self.saved_states.front() {
+            if *saved_frame >= confirmed_frame {
+                break;
+            }
+            self.saved_states.pop_front();
+        }
+        self.used_remote_inputs = self.used_remote_inputs.split_off(&confirmed_frame);
+        self.remote_inputs = self.remote_inputs.split_off(&confirmed_frame);
+        self.local_inputs = self.local_inputs.split_off(&self.acknowledged_frames.min(confirmed_frame));
+    }
+
+    // Sends every local input not acknowledged yet, so that a lost message
+    // is made up for by the next one.
+    fn send(&mut self) {
+        let inputs: Vec<Input> = self.local_inputs.range(self.acknowledged_frames..)
+            .zip(self.acknowledged_frames..)
+            .take_while(|((&frame, _), expected_frame)| frame == *expected_frame)
+            .map(|((_, &input), _)| input)
+            .collect();
+        self.transport.send(Message {
+            player: self.local_player,
+            start_frame: self.acknowledged_frames,
+            inputs,
+            ack: self.confirmed_frames,
+        });
+    }
+}
+
+// Network conditions simulated by the loopback transport.
+#[derive(Clone, Copy)]
+pub struct LinkConditions {
+    pub latency_ms: f64,
+    pub jitter_ms: f64,
+    // Probability, between 0 and 1, for a message to be dropped.
+    pub loss: f64,
+}
+
+struct InFlight {
+    delivery_ms: f64,
+    message: Message,
+}
+
+struct Link {
+    now_ms: f64,
+    conditions: LinkConditions,
+    in_flight: [Vec<InFlight>; 2],
+    rng: StdRng,
+}
+
+// In memory transport between two peers on the same machine. Time only
+// moves with `advance_time`, which makes runs reproducible for a given
+// seed.
+pub struct LoopbackTransport {
+    link: Rc<RefCell<Link>>,
+    side: usize,
+}
+
+impl LoopbackTransport {
+    pub fn pair(conditions: LinkConditions, seed: u64) -> (Self, Self) {
+        let link = Rc::new(RefCell::new(Link {
+            now_ms: 0.,
+            conditions,
+            in_flight: [Vec::new(), Vec::new()],
+            rng: StdRng::seed_from_u64(seed),
+        }));
+        (
+            LoopbackTransport {link: Rc::clone(&link), side: 0},
+            LoopbackTransport {link, side: 1},
+        )
+    }
+
+    // The clock is shared by both ends of the link.
+    pub fn advance_time(&mut self, elapsed_ms: f64) {
+        self.link.borrow_mut().now_ms += elapsed_ms;
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&mut self, message: Message) {
+        let mut link = self.link.borrow_mut();
+        let conditions = link.conditions;
+        if link.rng.gen_bool(conditions.loss.clamp(0., 1.)) {
+            return;
+        }
+        let jitter = if conditions.jitter_ms > 0. {
+            link.rng.gen_range(-conditions.jitter_ms..conditions.jitter_ms)
+        } else {
+            0.
+        };
+        let delivery_ms = link.now_ms + (conditions.latency_ms + jitter).max(0.);
+        link.in_flight[1 - self.side].push(InFlight {delivery_ms, message});
+    }
+
+    fn receive(&mut self) -> Vec<Message> {
+        let mut link = self.link.borrow_mut();
+        let now_ms = link.now_ms;
+        let in_flight = std::mem::take(&mut link.in_flight[self.side]);
+        let (mut delivered, pending): (Vec<InFlight>, Vec<InFlight>) = in_flight.into_iter()
+            .partition(|in_flight| in_flight.delivery_ms <= now_ms);
+        link.in_flight[self.side] = pending;
+        delivered.sort_by(|a, b| a.delivery_ms.partial_cmp(&b.delivery_ms).unwrap());
+        delivered.into_iter().map(|in_flight| in_flight.message).collect()
+    }
+}
@@ -0,0 +1,81 @@
+use super::board::Board;
+use super::game::Game;
+use super::game::State;
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+
+// Bumped whenever the saved game changes shape. Saves of other versions
+// are not loaded.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Save<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct SavedGame {
+    game: Game,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    UnsupportedVersion(u32),
+    Invalid(serde_json::Error),
+    // The board is not the one games are played on.
+    WrongBoardSize {height: usize, width: usize},
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::UnsupportedVersion(version) => write!(formatter, "unsupported save version {}", version),
+            LoadError::Invalid(error) => write!(formatter, "invalid save: {}", error),
+            LoadError::WrongBoardSize {height, width} => write!(formatter, "wrong board size {}x{}", width, height),
+        }
+    }
+}
+
+// Everything needed to go on with the game later, as JSON: the board,
+// pieces, piece generator, counters, timer and mode. Undo history is lost.
+pub fn save(game: &Game) -> String {
+    serde_json::to_string(&Save {version: VERSION, game}).unwrap()
+}
+
+// The loaded game is paused, whatever it was doing when saved. Saves that
+// could not have been written by a game, e.g. edited by hand, fail to load
+// rather than break the game later on.
+pub fn load(text: &str) -> Result<Game, LoadError> {
+    let SaveVersion {version} = serde_json::from_str(text).map_err(LoadError::Invalid)?;
+    if VERSION != version {
+        return Err(LoadError::UnsupportedVersion(version));
+    }
+    let SavedGame {mut game} = serde_json::from_str(text).map_err(LoadError::Invalid)?;
+    let board = game.board();
+    if !has_game_size(board) {
+        return Err(LoadError::WrongBoardSize {height: board.height(), width: board.width()});
+    }
+    if State::Running == game.state() {
+        game.pause();
+    }
+    Ok(game)
+}
+
+// The size of the boards of new games, every row of cells included.
+fn has_game_size(board: &Board) -> bool {
+    let game = Game::with_seed(0);
+    let expected = game.board();
+    expected.height() == board.height()
+        && expected.width() == board.width()
+        && expected.max_piece_size() == board.max_piece_size()
+        && expected.cells().len() == board.cells().len()
+        && board.cells().iter().all(|row| expected.width() == row.len())
+}
@@ -0,0 +1,188 @@
+//! Boards as standalone SVG images, for documentation and bug reports.
+
+use super::board::Board;
+use super::draw;
+use super::draw::Renderer;
+use super::piece::Piece;
+use super::theme::CellStyle;
+use super::theme::Theme;
+
+use std::fmt::Write;
+
+// `board` as an SVG document, see `draw::draw_position_with_ghost`.
+pub fn board_to_svg(board: &Board, piece: Option<&Piece>, ghost: bool, theme: &Theme) -> String {
+    let mut renderer = SvgRenderer::new(draw::ZOOM).with_theme(theme.clone());
+    draw::draw_position_with_ghost(board, piece, ghost, &mut renderer);
+    renderer.finish()
+}
+
+// Draws like `draw::CanvasRenderer`, each cell `zoom` pixels wide with a
+// one pixel line between cells, into SVG elements.
+pub struct SvgRenderer {
+    zoom: usize,
+    rows: usize,
+    columns: usize,
+    elements: String,
+    theme: Theme,
+}
+
+impl SvgRenderer {
+    pub fn new(zoom: usize) -> Self {
+        SvgRenderer {zoom, rows: 0, columns: 0, elements: String::new(), theme: Theme::classic()}
+    }
+
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    // The document of the last frame.
+    pub fn finish(self) -> String {
+        let width = self.x(self.columns) + 1;
+        let height = self.y(0) + 1;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n\
+             {2}</svg>\n",
+            width,
+            height,
+            self.elements,
+        )
+    }
+
+    fn y(&self, row: usize) -> usize {
+        draw::line_y(self.rows, row, self.zoom)
+    }
+
+    fn x(&self, column: usize) -> usize {
+        draw::line_x(column, self.zoom)
+    }
+
+    // Lines are drawn through the middle of the pixels between cells.
+    fn line(&mut self, from: (usize, usize), to: (usize, usize), color: &str) {
+        let _ = writeln!(
+            self.elements,
+            "<line x1=\"{}.5\" y1=\"{}.5\" x2=\"{}.5\" y2=\"{}.5\" stroke=\"{}\"/>",
+            from.0, from.1, to.0, to.1, escape(color),
+        );
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.rows = rows;
+        self.columns = columns;
+        self.elements.clear();
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let _ = writeln!(
+            self.elements,
+            "<rect x=\"{}\" y=\"{}\" width=\"{3}\" height=\"{3}\" fill=\"{}\"/>",
+            self.x(column) + 1,
+            self.y(row + 1) + 1,
+            escape(color),
+            self.zoom,
+        );
+    }
+
+    fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    fn background(&mut self, color: &str) {
+        let _ = writeln!(
+            self.elements,
+            "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            self.x(self.columns) + 1,
+            self.y(0) + 1,
+            escape(color),
+        );
+    }
+
+    fn styled_cell(&mut self, row: usize, column: usize, color: &str, style: CellStyle) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let (x, y, size) = (self.x(column) + 1, self.y(row + 1) + 1, self.zoom);
+        let edge = draw::edge_width(self.zoom);
+        match style {
+            CellStyle::Flat => self.cell(row, column, color),
+            CellStyle::Beveled => {
+                self.cell(row, column, color);
+                let _ = writeln!(
+                    self.elements,
+                    "<path d=\"M{0} {1}h{2}v{3}h-{4}v{4}h-{3}z\" fill=\"white\" fill-opacity=\"0.45\"/>",
+                    x, y, size, edge, size - edge,
+                );
+                let _ = writeln!(
+                    self.elements,
+                    "<path d=\"M{0} {1}h-{2}v-{3}h{4}v-{4}h{3}z\" fill=\"black\" fill-opacity=\"0.35\"/>",
+                    x + size, y + size, size - edge, edge, size - 2 * edge,
+                );
+            },
+            CellStyle::Outlined => {
+                let _ = writeln!(
+                    self.elements,
+                    "<rect x=\"{0}\" y=\"{1}\" width=\"{3}\" height=\"{3}\" fill=\"none\" stroke=\"{2}\" stroke-width=\"{4}\"/>",
+                    x as f64 + edge as f64 / 2.,
+                    y as f64 + edge as f64 / 2.,
+                    escape(color),
+                    size - edge,
+                    edge,
+                );
+            },
+        }
+    }
+
+    fn glyph(&mut self, row: usize, column: usize, glyph: char, color: &str) {
+        if row >= self.rows || column >= self.columns {
+            return;
+        }
+        let _ = writeln!(
+            self.elements,
+            "<text x=\"{}\" y=\"{}\" fill=\"{}\" font-family=\"sans-serif\" font-weight=\"bold\" font-size=\"{}\" \
+             text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+            self.x(column) + self.zoom.div_ceil(2),
+            self.y(row + 1) + self.zoom.div_ceil(2),
+            escape(color),
+            self.zoom * 2 / 3,
+            escape(&glyph.to_string()),
+        );
+    }
+
+    fn grid(&mut self, color: &str) {
+        for row in 0..=self.rows {
+            self.line((0, self.y(row)), (self.x(self.columns), self.y(row)), color);
+        }
+        for column in 0..=self.columns {
+            self.line((self.x(column), 0), (self.x(column), self.y(0)), color);
+        }
+    }
+
+    fn horizontal_line(&mut self, row: usize, color: &str) {
+        self.line((0, self.y(row)), (self.x(self.columns), self.y(row)), color);
+    }
+
+    fn text(&mut self, row: usize, text: &str, color: &str) {
+        let _ = writeln!(
+            self.elements,
+            "<text x=\"{}\" y=\"{}\" fill=\"{}\" font-family=\"sans-serif\" font-weight=\"bold\" font-size=\"{}\" \
+             text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>",
+            self.x(self.columns) / 2,
+            self.y(row) - self.zoom.div_ceil(2),
+            escape(color),
+            self.zoom,
+            escape(text),
+        );
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,309 @@
+//! Colors and styles boards are drawn with, built in or loaded from JSON.
+//!
+//! A JSON theme only needs the fields it changes, the others are those of
+//! the classic theme:
+//!
+//! ```json
+//! {"background": "black", "pieces": {"I": "#0FF"}, "cell_style": "beveled"}
+//! ```
+
+use super::piece;
+use super::piece::Type;
+
+use serde::Deserialize;
+use serde::Serialize;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CellStyle {
+    Flat,
+    // Lighter on the top and left edges, darker on the others.
+    Beveled,
+    // Only the edges.
+    Outlined,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GhostStyle {
+    Hidden,
+    // Filled with the ghost color.
+    Solid,
+    // Edges in the ghost color.
+    Outline,
+    // Edges in the color of the piece.
+    PieceOutline,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PieceColors {
+    #[serde(rename = "I")]
+    pub i: String,
+    #[serde(rename = "T")]
+    pub t: String,
+    #[serde(rename = "O")]
+    pub o: String,
+    #[serde(rename = "L")]
+    pub l: String,
+    #[serde(rename = "J")]
+    pub j: String,
+    #[serde(rename = "S")]
+    pub s: String,
+    #[serde(rename = "Z")]
+    pub z: String,
+    #[serde(rename = "G")]
+    pub garbage: String,
+}
+
+impl PieceColors {
+    pub fn get(&self, piece_type: Type) -> &str {
+        match piece_type {
+            Type::I => &self.i,
+            Type::T => &self.t,
+            Type::O => &self.o,
+            Type::L => &self.l,
+            Type::J => &self.j,
+            Type::S => &self.s,
+            Type::Z => &self.z,
+            Type::Garbage => &self.garbage,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub pieces: PieceColors,
+    pub empty: String,
+    // Behind the cells, seen between them.
+    pub background: String,
+    pub grid: String,
+    // Line between the board and the rows above it.
+    pub limit: String,
+    pub text: String,
+    pub ghost: String,
+    pub ghost_style: GhostStyle,
+    pub cell_style: CellStyle,
+    // Cleared rows flash with it before they go.
+    pub line_clear: String,
+    // Incoming garbage in the meter beside the board, ready to rise or still
+    // waiting.
+    pub garbage_ready: String,
+    pub garbage_pending: String,
+    // Whether pieces show their letter, for players telling them apart by
+    // something other than color.
+    pub glyphs: bool,
+    pub glyph_color: String,
+}
+
+impl Default for PieceColors {
+    fn default() -> Self {
+        Theme::classic().pieces
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::classic()
+    }
+}
+
+fn colors(colors: [&str; 8]) -> PieceColors {
+    let [i, t, o, l, j, s, z, garbage] = colors;
+    PieceColors {
+        i: i.to_string(),
+        t: t.to_string(),
+        o: o.to_string(),
+        l: l.to_string(),
+        j: j.to_string(),
+        s: s.to_string(),
+        z: z.to_string(),
+        garbage: garbage.to_string(),
+    }
+}
+
+impl Theme {
+    // Flat CSS colors on a light board, as the game always looked.
+    pub fn classic() -> Self {
+        Theme {
+            pieces: colors(["cyan", "purple", "yellow", "orange", "blue", "lime", "red", "gray"]),
+            empty: "#DDD".to_string(),
+            background: "white".to_string(),
+            grid: "#AAA".to_string(),
+            limit: "#F00".to_string(),
+            text: "black".to_string(),
+            ghost: "#888".to_string(),
+            ghost_style: GhostStyle::Solid,
+            cell_style: CellStyle::Flat,
+            line_clear: "white".to_string(),
+            garbage_ready: "#F00".to_string(),
+            garbage_pending: "orange".to_string(),
+            glyphs: false,
+            glyph_color: "black".to_string(),
+        }
+    }
+
+    // Colors of the Tetris guideline, beveled on a dark board.
+    pub fn guideline() -> Self {
+        Theme {
+            pieces: colors(["#00F0F0", "#A000F0", "#F0F000", "#F0A000", "#0000F0", "#00F000", "#F00000", "#808080"]),
+            empty: "#111".to_string(),
+            background: "black".to_string(),
+            grid: "#333".to_string(),
+            limit: "#F00".to_string(),
+            text: "white".to_string(),
+            ghost: "#CCC".to_string(),
+            ghost_style: GhostStyle::PieceOutline,
+            cell_style: CellStyle::Beveled,
+            line_clear: "white".to_string(),
+            garbage_ready: "#F00".to_string(),
+            garbage_pending: "#F0A000".to_string(),
+            glyphs: false,
+            glyph_color: "black".to_string(),
+        }
+    }
+
+    // Saturated colors, all far from each other and from the black board,
+    // with outlined cells so that neighbours stand apart.
+    pub fn high_contrast() -> Self {
+        Theme {
+            pieces: colors(["#00FFFF", "#FF00FF", "#FFFF00", "#FF8800", "#3399FF", "#00FF00", "#FF0000", "#BBBBBB"]),
+            empty: "black".to_string(),
+            background: "black".to_string(),
+            grid: "#555".to_string(),
+            limit: "white".to_string(),
+            text: "white".to_string(),
+            ghost: "white".to_string(),
+            ghost_style: GhostStyle::Outline,
+            cell_style: CellStyle::Outlined,
+            line_clear: "white".to_string(),
+            garbage_ready: "#FF0000".to_string(),
+            garbage_pending: "#FF8800".to_string(),
+            glyphs: false,
+            glyph_color: "white".to_string(),
+        }
+    }
+
+    // Protanopes and deuteranopes mix up the same reds and greens: both get
+    // the Okabe-Ito colors, told apart by hue along the blue-yellow axis and
+    // by lightness.
+    pub fn deuteranopia() -> Self {
+        color_blind(colors(["#56B4E9", "#CC79A7", "#F0E442", "#E69F00", "#0072B2", "#009E73", "#D55E00", "#999999"]))
+    }
+
+    // Reds look darker to protanopes than to deuteranopes, so the Z piece is
+    // a lighter vermillion than in the deuteranopia palette.
+    pub fn protanopia() -> Self {
+        color_blind(colors(["#56B4E9", "#CC79A7", "#F0E442", "#E69F00", "#0072B2", "#009E73", "#F26B1D", "#999999"]))
+    }
+
+    // Tritanopes mix up blues with greens and yellows with pinks: colors
+    // differ along the red-green axis and in lightness instead.
+    pub fn tritanopia() -> Self {
+        color_blind(colors(["#66CCEE", "#AA3377", "#CCBB44", "#EE7733", "#0044AA", "#228833", "#CC3311", "#999999"]))
+    }
+
+    // Fails on colors images could not be drawn with, see `rgb`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let theme: Theme = serde_json::from_str(json)?;
+        match theme.colors().into_iter().find(|color| rgb(color).is_none()) {
+            Some(color) => Err(serde::de::Error::custom(format!("unknown color {}", color))),
+            None => Ok(theme),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("themes are plain data")
+    }
+
+    fn colors(&self) -> Vec<&str> {
+        let pieces = &self.pieces;
+        [
+            &pieces.i, &pieces.t, &pieces.o, &pieces.l, &pieces.j, &pieces.s, &pieces.z, &pieces.garbage,
+            &self.empty, &self.background, &self.grid, &self.limit, &self.text, &self.ghost, &self.line_clear,
+            &self.garbage_ready, &self.garbage_pending, &self.glyph_color,
+        ].iter().map(|color| color.as_str()).collect()
+    }
+
+    pub fn cell_color(&self, cell: &piece::Cell) -> &str {
+        match cell.0 {
+            Some(piece_type) => self.pieces.get(piece_type),
+            None => &self.empty,
+        }
+    }
+}
+
+// Color blind palettes on a dark board, with glyphs as colors alone are
+// never enough.
+fn color_blind(pieces: PieceColors) -> Theme {
+    Theme {
+        pieces,
+        empty: "#222".to_string(),
+        background: "black".to_string(),
+        grid: "#444".to_string(),
+        limit: "white".to_string(),
+        text: "white".to_string(),
+        ghost: "#666".to_string(),
+        ghost_style: GhostStyle::Solid,
+        cell_style: CellStyle::Flat,
+        line_clear: "white".to_string(),
+        garbage_ready: "#D55E00".to_string(),
+        garbage_pending: "#F0E442".to_string(),
+        glyphs: true,
+        glyph_color: "black".to_string(),
+    }
+}
+
+// The CSS colors themes can have, named or `#RGB` and `#RRGGBB`.
+pub fn rgb(color: &str) -> Option<[u8; 3]> {
+    match color {
+        "black" => return Some([0x00, 0x00, 0x00]),
+        "white" => return Some([0xFF, 0xFF, 0xFF]),
+        "cyan" => return Some([0x00, 0xFF, 0xFF]),
+        "purple" => return Some([0x80, 0x00, 0x80]),
+        "yellow" => return Some([0xFF, 0xFF, 0x00]),
+        "orange" => return Some([0xFF, 0xA5, 0x00]),
+        "blue" => return Some([0x00, 0x00, 0xFF]),
+        "lime" => return Some([0x00, 0xFF, 0x00]),
+        "red" => return Some([0xFF, 0x00, 0x00]),
+        "gray" => return Some([0x80, 0x80, 0x80]),
+        _ => {},
+    }
+    let digits = color.strip_prefix('#')?.chars()
+        .map(|digit| digit.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    match digits[..] {
+        [r, g, b] => Some([r * 0x11, g * 0x11, b * 0x11]),
+        [r1, r2, g1, g2, b1, b2] => Some([r1 << 4 | r2, g1 << 4 | g2, b1 << 4 | b2]),
+        _ => None,
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Theme> {
+    match name {
+        "classic" => Some(Theme::classic()),
+        "guideline" => Some(Theme::guideline()),
+        "high-contrast" => Some(Theme::high_contrast()),
+        "deuteranopia" => Some(Theme::deuteranopia()),
+        "protanopia" => Some(Theme::protanopia()),
+        "tritanopia" => Some(Theme::tritanopia()),
+        _ => None,
+    }
+}
+
+// Drawn inside the cells of pieces when glyphs are on. Garbage has none,
+// its gray already standing apart.
+pub fn glyph(piece_type: Type) -> Option<char> {
+    match piece_type {
+        Type::Garbage => None,
+        piece_type => Some(piece_type.letter()),
+    }
+}
+
+// The classic theme, for renderers without a theme of their own.
+pub fn classic() -> &'static Theme {
+    static CLASSIC: OnceLock<Theme> = OnceLock::new();
+    CLASSIC.get_or_init(Theme::classic)
+}
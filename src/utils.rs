@@ -8,3 +8,12 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+// Logs to the browser console. Outside of wasm (headless runs, tests) there
+// is no console to import from, so messages are dropped.
+pub fn log(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::log_1(&message.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = message;
+}
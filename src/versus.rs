@@ -0,0 +1,139 @@
+use super::attack::AttackTable;
+use super::game::Delays;
+use super::game::Event;
+use super::game::Game;
+use super::game::State;
+use super::game_loop::TICKS_PER_SECOND;
+use super::garbage::GarbageQueue;
+use super::garbage::HoleGenerator;
+use super::input;
+use super::input::Input;
+use super::rollback::Simulation;
+
+use rand::thread_rng;
+use rand::Rng;
+
+pub const DEFAULT_GARBAGE_DELAY_TICKS: u32 = TICKS_PER_SECOND / 2;
+
+#[derive(Clone)]
+struct Player {
+    game: Game,
+    incoming: GarbageQueue,
+    holes: HoleGenerator,
+}
+
+impl Player {
+    fn new(attack_table: Box<dyn AttackTable>, garbage_delay_ticks: u32, seed: u64, hole_seed: u64) -> Self {
+        let mut game = Game::with_seed(seed);
+        game.set_attack_table(attack_table);
+        game.set_delays(Delays::NONE);
+        // Nothing to undo, and rollback clones the games every frame.
+        game.set_history_capacity(0);
+        game.record_events();
+        let width = game.board().width();
+        Player {
+            game,
+            incoming: GarbageQueue::new(garbage_delay_ticks),
+            holes: HoleGenerator::with_seed(width, 1., hole_seed),
+        }
+    }
+
+    // Every ready batch comes in as rows sharing a single hole column.
+    fn receive_garbage(&mut self) {
+        for lines in self.incoming.take_ready() {
+            let hole_column = self.holes.next_hole_column();
+            self.game.push_garbage(&vec!(hole_column; lines as usize));
+        }
+    }
+}
+
+// Two local games sending garbage to each other. Line clears first cancel
+// the garbage waiting for their player, the rest is sent to the opponent
+// who receives it on their next piece that clears nothing.
+#[derive(Clone)]
+pub struct Versus {
+    players: [Player; 2],
+}
+
+impl Versus {
+    pub fn new(attack_table: Box<dyn AttackTable>, garbage_delay_ticks: u32) -> Self {
+        Self::with_seed(attack_table, garbage_delay_ticks, thread_rng().gen())
+    }
+
+    // Both players get the same pieces. A versus is entirely determined by
+    // its seed and the inputs of the players, which rollback relies on.
+    pub fn with_seed(attack_table: Box<dyn AttackTable>, garbage_delay_ticks: u32, seed: u64) -> Self {
+        Versus {
+            players: [
+                Player::new(attack_table.clone(), garbage_delay_ticks, seed, seed.wrapping_add(1)),
+                Player::new(attack_table, garbage_delay_ticks, seed, seed.wrapping_add(2)),
+            ],
+        }
+    }
+
+    pub fn run(&mut self) {
+        for player in self.players.iter_mut() {
+            player.game.run();
+        }
+    }
+
+    pub fn pause(&mut self) {
+        for player in self.players.iter_mut() {
+            player.game.pause();
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for player in self.players.iter_mut() {
+            player.game.tick();
+            player.incoming.tick();
+        }
+        self.exchange();
+    }
+
+    pub fn game(&self, player: usize) -> &Game {
+        &self.players[player].game
+    }
+
+    pub fn game_mut(&mut self, player: usize) -> &mut Game {
+        &mut self.players[player].game
+    }
+
+    pub fn garbage_queue(&self, player: usize) -> &GarbageQueue {
+        &self.players[player].incoming
+    }
+
+    fn exchange(&mut self) {
+        for index in 0..self.players.len() {
+            let opponent = 1 - index;
+            for event in self.players[index].game.take_events() {
+                match event {
+                    Event::Lock {cleared_rows, attack, ..} => {
+                        let player = &mut self.players[index];
+                        let attack = player.incoming.cancel(attack);
+                        if cleared_rows.is_empty() {
+                            player.receive_garbage();
+                        }
+                        self.players[opponent].incoming.push(attack);
+                    },
+                    Event::TopOut => {
+                        let opponent = &mut self.players[opponent].game;
+                        if State::Running == opponent.state() {
+                            opponent.finish();
+                        }
+                    },
+                    Event::Finish => {},
+                }
+            }
+        }
+    }
+}
+
+impl Simulation for Versus {
+    fn advance(&mut self, inputs: &[Input]) {
+        for (player, &player_input) in inputs.iter().enumerate() {
+            input::apply(self.game_mut(player), player_input);
+        }
+        self.tick();
+    }
+}
@@ -0,0 +1,166 @@
+//! Lines sent by each attack table, and the T-spins, combos and back to
+//! backs games count them from.
+
+mod common;
+
+use tetris::attack::AttackTable;
+use tetris::attack::Clear;
+use tetris::attack::Classic;
+use tetris::attack::Guideline;
+use tetris::attack::TSpin;
+use tetris::attack::TetrioLike;
+use tetris::board::Board;
+use tetris::game::Event;
+use tetris::game::Game;
+use tetris::piece::Piece;
+use tetris::piece::Type;
+
+fn clear(lines: usize, t_spin: TSpin, combo: u32, back_to_back: u32, perfect_clear: bool) -> Clear {
+    Clear {lines, t_spin, combo, back_to_back, perfect_clear}
+}
+
+fn assert_attacks(table: &dyn AttackTable, cases: &[(Clear, u32)]) {
+    for (clear, attack) in cases {
+        assert_eq!(*attack, table.attack(clear), "{:?}", clear);
+    }
+}
+
+#[test]
+fn guideline_attacks() {
+    use TSpin::*;
+    assert_attacks(&Guideline, &[
+        (clear(0, None, 0, 0, false), 0),
+        (clear(0, Full, 5, 3, false), 0),
+        (clear(1, None, 0, 0, false), 0),
+        (clear(2, None, 0, 0, false), 1),
+        (clear(3, None, 0, 0, false), 2),
+        (clear(4, None, 0, 0, false), 4),
+        (clear(1, Mini, 0, 0, false), 0),
+        (clear(2, Mini, 0, 0, false), 1),
+        (clear(1, Full, 0, 0, false), 2),
+        (clear(2, Full, 0, 0, false), 4),
+        (clear(3, Full, 0, 0, false), 6),
+        // Back to back adds one line however long the chain.
+        (clear(4, None, 0, 1, false), 5),
+        (clear(2, Full, 0, 7, false), 5),
+        // Combos by the table, its last entry for longer ones.
+        (clear(1, None, 1, 0, false), 0),
+        (clear(1, None, 2, 0, false), 1),
+        (clear(1, None, 9, 0, false), 4),
+        (clear(1, None, 11, 0, false), 5),
+        (clear(1, None, 40, 0, false), 5),
+        (clear(4, None, 0, 0, true), 14),
+        (clear(2, None, 3, 2, true), 1 + 1 + 1 + 10),
+    ]);
+}
+
+#[test]
+fn tetrio_like_attacks() {
+    use TSpin::*;
+    assert_attacks(&TetrioLike, &[
+        (clear(0, None, 3, 0, false), 0),
+        (clear(1, None, 0, 0, false), 0),
+        (clear(4, None, 0, 0, false), 4),
+        (clear(2, Full, 0, 0, false), 4),
+        // Back to back grows with the chain: 1 + ln(1 + 0.8 chain).
+        (clear(4, None, 0, 1, false), 5),
+        (clear(4, None, 0, 3, false), 6),
+        (clear(4, None, 0, 20, false), 7),
+        // Combos multiply the attack, or count alone after singles.
+        (clear(4, None, 4, 0, false), 8),
+        (clear(2, None, 2, 0, false), 1),
+        (clear(1, None, 1, 0, false), 0),
+        (clear(1, None, 2, 0, false), 1),
+        (clear(1, None, 6, 0, false), 2),
+        (clear(1, None, 0, 0, true), 10),
+    ]);
+}
+
+#[test]
+fn classic_attacks() {
+    use TSpin::*;
+    assert_attacks(&Classic, &[
+        (clear(0, None, 0, 0, false), 0),
+        (clear(1, None, 0, 0, false), 0),
+        (clear(2, None, 0, 0, false), 1),
+        (clear(3, None, 0, 0, false), 2),
+        (clear(4, None, 0, 0, false), 4),
+        // Nothing but lines counts.
+        (clear(2, Full, 5, 3, true), 1),
+        (clear(4, None, 9, 9, true), 4),
+    ]);
+}
+
+// Locks the piece and returns what the lock was worth.
+fn lock(game: &mut Game) -> Clear {
+    common::lock(game);
+    game.take_events().into_iter()
+        .find_map(|event| match event {
+            Event::Lock {clear, ..} => Some(clear),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn game() -> Game {
+    let mut game = Game::with_seed(1);
+    game.record_events();
+    game.run();
+    game
+}
+
+// Turns a T into its slot, pointing down at the bottom left of `board`, and
+// drops it there unless `rotated`.
+fn t_in_slot(game: &mut Game, board: &str, rotated: bool) -> Clear {
+    game.set_board(Board::from_text(board, 20, 10).unwrap());
+    let mut piece = Piece::new(0, 0, Type::T);
+    piece.rotate_clockwise();
+    piece.rotate_clockwise();
+    if rotated {
+        piece.rotate_anticlockwise();
+    }
+    game.set_piece(piece);
+    if rotated {
+        game.rotate_clockwise();
+    }
+    lock(game)
+}
+
+#[test]
+fn t_spins_need_three_corners_and_a_rotation() {
+    let cases = [
+        // Both corners in front of the T and one behind it.
+        ("G.........\n...GGGGGGG\nG.GGGGGGGG", true, TSpin::Full, 2),
+        ("G.G.......\n...GGGGGGG\nG.GGGGGGGG", true, TSpin::Full, 2),
+        // One corner in front.
+        ("G.G.......\n...GGGGGGG\nG.........", true, TSpin::Mini, 1),
+        // Two corners only.
+        ("..........\n...GGGGGGG\nG.GGGGGGGG", true, TSpin::None, 2),
+        // Dropped in rather than turned.
+        ("G.........\n...GGGGGGG\nG.GGGGGGGG", false, TSpin::None, 2),
+    ];
+    for (board, rotated, t_spin, lines) in cases.iter() {
+        let clear = t_in_slot(&mut game(), board, *rotated);
+        assert_eq!((*t_spin, *lines), (clear.t_spin, clear.lines), "{}", board);
+    }
+}
+
+#[test]
+fn combos_and_back_to_backs_are_counted_across_locks() {
+    let t_spin_double = "G.........\n...GGGGGGG\nG.GGGGGGGG";
+    let o_in_well = |game: &mut Game, board: &str| {
+        common::o_over_the_middle(game, board);
+        lock(game)
+    };
+    let mut game = game();
+    let summary = |clear: Clear| (clear.lines, clear.combo, clear.back_to_back, clear.is_difficult());
+
+    assert_eq!((2, 0, 0, true), summary(t_in_slot(&mut game, t_spin_double, true)));
+    assert_eq!((2, 1, 1, true), summary(t_in_slot(&mut game, t_spin_double, true)));
+    // A double keeps the combo going but ends the back to back.
+    assert_eq!((2, 2, 0, false), summary(o_in_well(&mut game, "GGGG..GGGG\nGGGG..GGGG")));
+    assert_eq!((2, 3, 0, true), summary(t_in_slot(&mut game, t_spin_double, true)));
+    // A lock clearing nothing ends the combo, not the back to back.
+    assert_eq!((0, 0, 0, false), summary(o_in_well(&mut game, "")));
+    assert_eq!((2, 0, 1, true), summary(t_in_slot(&mut game, t_spin_double, true)));
+}
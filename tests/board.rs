@@ -0,0 +1,47 @@
+//! Boards written as text grids, as used by test fixtures and pasted
+//! positions.
+
+use tetris::board::Board;
+use tetris::board::ParseError;
+
+#[test]
+fn text_round_trips_through_a_board() {
+    let text = "\
+        ....\n\
+        .T..\n\
+        TTTI\n\
+        GG.I\n";
+    let board = Board::from_text(text, 4, 4).unwrap();
+    assert_eq!(text, board.to_text());
+}
+
+#[test]
+fn rows_are_read_from_the_bottom() {
+    let board = Board::from_text("
+        Z...
+        GGG.
+    ", 20, 4).unwrap();
+    assert!(board.is_occupied(0, 0));
+    assert!(!board.is_occupied(0, 3));
+    assert!(board.is_occupied(1, 0));
+    assert!(!board.is_occupied(1, 1));
+    assert!(!board.is_occupied(2, 0));
+    assert_eq!(1, board.garbage_row_count());
+    assert_eq!(20, board.to_text().lines().count());
+}
+
+#[test]
+fn cells_above_the_visible_rows_are_rendered() {
+    let board = Board::from_text("O.\nO.\n..\n..\n", 2, 2).unwrap();
+    assert_eq!("O.\nO.\n..\n..\n", board.to_text());
+}
+
+#[test]
+fn invalid_grids_are_rejected() {
+    assert_eq!(Err(ParseError::WrongWidth {row: 1, width: 3}), Board::from_text("....\n...\n", 20, 4).map(|_| ()));
+    assert_eq!(
+        Err(ParseError::UnknownCell {row: 0, column: 2, character: 'X'}),
+        Board::from_text("..X.\n", 20, 4).map(|_| ()),
+    );
+    assert_eq!(Err(ParseError::TooManyRows {rows: 7, max_rows: 6}), Board::from_text(&"..\n".repeat(7), 2, 2).map(|_| ()));
+}
@@ -0,0 +1,99 @@
+//! Renderer double and game setups shared by the tests.
+
+// Each test crate only uses some of them.
+#![allow(dead_code)]
+
+use tetris::board::Board;
+use tetris::draw::Renderer;
+use tetris::game::Game;
+use tetris::piece::Piece;
+use tetris::piece::Type;
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Call {
+    Clear { rows: usize, columns: usize },
+    Cell { row: usize, column: usize, color: String },
+    Grid,
+    HorizontalLine { row: usize },
+    Text { row: usize, text: String },
+    EndFrame,
+}
+
+// Records the calls reaching it, drawing nothing.
+#[derive(Default)]
+pub struct RecordingRenderer {
+    pub calls: Vec<Call>,
+}
+
+impl RecordingRenderer {
+    // Cells in another color than the one of empty cells, in drawing order.
+    pub fn colored_cells(&self) -> Vec<(usize, usize, &str)> {
+        self.calls.iter()
+            .filter_map(|call| match call {
+                Call::Cell {row, column, color} if "#DDD" != color => Some((*row, *column, color.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Cells filled with the background before being drawn again.
+    pub fn redrawn_cells(&self) -> BTreeSet<(usize, usize)> {
+        self.calls.iter()
+            .filter_map(|call| match call {
+                Call::Cell {row, column, color} if "white" == color => Some((*row, *column)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn clear(&mut self, rows: usize, columns: usize) {
+        self.calls.push(Call::Clear {rows, columns});
+    }
+
+    fn cell(&mut self, row: usize, column: usize, color: &str) {
+        self.calls.push(Call::Cell {row, column, color: color.to_string()});
+    }
+
+    fn grid(&mut self, _color: &str) {
+        self.calls.push(Call::Grid);
+    }
+
+    fn horizontal_line(&mut self, row: usize, _color: &str) {
+        self.calls.push(Call::HorizontalLine {row});
+    }
+
+    fn text(&mut self, row: usize, text: &str, _color: &str) {
+        self.calls.push(Call::Text {row, text: text.to_string()});
+    }
+
+    fn end_frame(&mut self) {
+        self.calls.push(Call::EndFrame);
+    }
+}
+
+// A running game on the stack of `text`, see `Board::from_text`.
+pub fn game_with_board(text: &str) -> Game {
+    let mut game = Game::with_seed(3);
+    game.run();
+    game.set_board(Board::from_text(text, 20, 10).unwrap());
+    game
+}
+
+// Replaces the stack with the one of `text`, and the falling piece with an
+// O at the bottom of the two middle columns.
+pub fn o_over_the_middle(game: &mut Game, text: &str) {
+    game.set_board(Board::from_text(text, 20, 10).unwrap());
+    game.set_piece(Piece::new(0, 4, Type::O));
+}
+
+// Drops the falling piece until it locks.
+pub fn lock(game: &mut Game) {
+    let pieces = game.pieces();
+    while pieces == game.pieces() {
+        game.move_down();
+    }
+}
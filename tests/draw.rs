@@ -0,0 +1,82 @@
+//! Frames drawn by the game, checked with a renderer recording its calls.
+
+mod common;
+
+use common::game_with_board;
+use common::Call;
+use common::RecordingRenderer;
+use tetris::draw;
+use tetris::theme::Theme;
+
+#[test]
+fn board_frame_has_the_stack_and_the_falling_piece() {
+    let game = game_with_board("
+        GGGGGGGGG.
+        T.........
+    ");
+    let mut renderer = RecordingRenderer::default();
+    draw::draw_board(&game, &mut renderer);
+
+    assert_eq!(Call::Clear {rows: 24, columns: 10}, renderer.calls[0]);
+    assert!(renderer.calls.contains(&Call::Grid));
+    assert!(renderer.calls.contains(&Call::HorizontalLine {row: 20}));
+    let cells = renderer.colored_cells();
+    assert!(cells.contains(&(0, 0, "purple")));
+    assert!(cells.contains(&(1, 8, "gray")));
+    assert!(!cells.iter().any(|&(row, column, _)| 1 == row && 9 == column));
+    // Two stack rows, then the four cells of the falling piece on top.
+    assert_eq!(10 + 4, cells.len());
+    assert!(cells[10..].iter().all(|&(row, _, _)| row >= 19));
+    assert!(!renderer.calls.iter().any(|call| matches!(call, Call::Text {..})));
+}
+
+#[test]
+fn ghost_is_drawn_below_the_falling_piece_in_the_same_frame() {
+    let game = game_with_board("");
+    let mut renderer = RecordingRenderer::default();
+    draw::draw_board_with_ghost(&game, &mut renderer);
+
+    let cells = renderer.colored_cells();
+    assert_eq!(4 + 4, cells.len());
+    assert!(cells[..4].iter().all(|&(row, _, color)| row < 2 && "#888" == color));
+    assert!(cells[4..].iter().all(|&(row, _, color)| row >= 19 && "#888" != color));
+    assert_eq!(1, renderer.calls.iter().filter(|&call| &Call::EndFrame == call).count());
+    assert_eq!(Some(&Call::EndFrame), renderer.calls.last());
+}
+
+#[test]
+fn paused_board_says_so() {
+    let mut game = game_with_board("");
+    game.pause();
+    let mut renderer = RecordingRenderer::default();
+    draw::draw_board(&game, &mut renderer);
+    assert_eq!(&[Call::Text {row: 10, text: "Paused".to_string()}, Call::EndFrame], &renderer.calls[renderer.calls.len() - 2..]);
+}
+
+#[test]
+fn next_pieces_are_side_by_side() {
+    let game = game_with_board("");
+    let mut renderer = RecordingRenderer::default();
+    draw::draw_next_pieces(&game, &mut renderer);
+
+    assert_eq!(Call::Clear {rows: 4, columns: 12}, renderer.calls[0]);
+    let cells = renderer.colored_cells();
+    assert_eq!(12, cells.len());
+    let theme = Theme::classic();
+    for (index, piece) in game.next_pieces().iter().enumerate() {
+        let piece_cells = &cells[4 * index..4 * (index + 1)];
+        assert!(piece_cells.iter().all(|&(_, column, _)| column / 4 == index));
+        let color = theme.pieces.get(piece.piece_type());
+        assert!(piece_cells.iter().all(|&(_, _, cell_color)| color == cell_color));
+    }
+}
+
+#[test]
+fn zoom_fits_the_board_in_the_room_given() {
+    // 24 rows of 30 pixel cells and the lines around them take 745 pixels.
+    assert_eq!(30, draw::fit_zoom(24, 10, 2000., 745.));
+    assert_eq!(29, draw::fit_zoom(24, 10, 2000., 744.));
+    // Narrow rooms limit the zoom by their width instead.
+    assert_eq!(19, draw::fit_zoom(24, 10, 201., 745.));
+    assert_eq!(1, draw::fit_zoom(24, 10, 0., 0.));
+}
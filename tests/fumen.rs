@@ -0,0 +1,132 @@
+//! Fumen strings, checked against ones made by the fumen editor and through
+//! round trips.
+
+use tetris::board::Board;
+use tetris::fumen;
+use tetris::fumen::Page;
+use tetris::piece::Piece;
+use tetris::piece::Type;
+
+fn empty_page() -> Page {
+    Page {board: Board::new(20, 10), piece: None, comment: String::new()}
+}
+
+fn piece_summary(piece: &Option<Piece>) -> Option<(char, u8, i32, i32)> {
+    piece.as_ref().map(|piece| {
+        (piece.piece_type().letter(), piece.orientation() as u8, piece.row_offset(), piece.column_offset())
+    })
+}
+
+fn assert_same_pages(expected: &[Page], actual: &[Page]) {
+    assert_eq!(expected.len(), actual.len());
+    for (expected, actual) in expected.iter().zip(actual) {
+        assert_eq!(expected.board.to_text(), actual.board.to_text());
+        assert_eq!(piece_summary(&expected.piece), piece_summary(&actual.piece));
+        assert_eq!(expected.comment, actual.comment);
+    }
+}
+
+#[test]
+fn empty_field_matches_the_editor() {
+    assert_eq!("v115@vhAAgH", fumen::encode(&[empty_page()]));
+    let pages = fumen::decode("https://harddrop.com/fumen/?v115@vhAAgH").unwrap();
+    assert_same_pages(&[empty_page()], &pages);
+}
+
+#[test]
+fn repeated_fields_are_counted() {
+    let pages = [empty_page(), empty_page(), empty_page()];
+    assert_eq!("v115@vhCAgHAAAAAA", fumen::encode(&pages));
+    assert_same_pages(&pages, &fumen::decode("v115@vhCAgHAAAAAA").unwrap());
+}
+
+#[test]
+fn pages_round_trip() {
+    let board = Board::from_text("
+        ..........
+        T.........
+        TT....OO..
+        T..IIIIOOG
+    ", 20, 10).unwrap();
+    let mut pages = Vec::new();
+    for piece_type in [Type::I, Type::T, Type::O, Type::L, Type::J, Type::S, Type::Z].iter() {
+        for rotations in 0..4 {
+            let mut piece = Piece::new(10, 3, *piece_type);
+            for _ in 0..rotations {
+                piece.rotate_clockwise();
+            }
+            pages.push(Page {board: board.clone(), piece: Some(piece), comment: format!("{} rotated {} times", piece_type.letter(), rotations)});
+        }
+    }
+    pages.push(Page {board: Board::new(20, 10), piece: None, comment: "écran vidé 100%".to_string()});
+
+    let encoded = fumen::encode(&pages);
+    assert!(encoded.contains('?'));
+    assert_same_pages(&pages, &fumen::decode(&encoded).unwrap());
+}
+
+#[test]
+fn locked_pieces_carry_to_the_next_page() {
+    let mut piece = Piece::new(0, 0, Type::I);
+    piece.rotate_clockwise();
+    let first = Page {board: Board::new(20, 10), piece: Some(piece), comment: String::new()};
+    let second = Page {board: Board::from_text("..I.......\n..I.......\n..I.......\n..I.......\n", 20, 10).unwrap(), piece: None, comment: String::new()};
+    let encoded = fumen::encode(&[first, second]);
+    let pages = fumen::decode(&encoded).unwrap();
+    assert_eq!(pages[1].board.to_text(), Board::from_text("..I.......\n..I.......\n..I.......\n..I.......\n", 20, 10).unwrap().to_text());
+    // The second page only bumps the repeat count of the first field.
+    assert_eq!("v115@vhBZFJAAA", encoded);
+}
+
+#[test]
+fn broken_fumens_are_rejected() {
+    assert_eq!(Err(fumen::DecodeError::MissingPrefix), fumen::decode("vhAAgH").map(|_| ()));
+    assert_eq!(Err(fumen::DecodeError::UnexpectedEnd), fumen::decode("v115@vhAAg").map(|_| ()));
+    assert_eq!(Err(fumen::DecodeError::InvalidCharacter('!')), fumen::decode("v115@vh!AgH").map(|_| ()));
+}
+
+// The fumens below were laid out by hand from the v115 format, apart from
+// this crate's encoder.
+
+#[test]
+fn pieces_and_comments_are_decoded() {
+    // A T-spin double: the upside down T fills both rows.
+    let pages = fumen::decode("v115@RhC8CeH8AeE8JeFLYPAUE7QEJG98AQWrhECDdCA").unwrap();
+    assert_eq!(1, pages.len());
+    let page = &pages[0];
+    assert_eq!(Board::from_text("GGG...GGGG\nGGGG.GGGGG", 20, 10).unwrap().to_text(), page.board.to_text());
+    assert_eq!("T-spin double", page.comment);
+    let piece = page.piece.clone().unwrap();
+    assert_eq!('T', piece.piece_type().letter());
+    let mut board = page.board.clone();
+    board.freeze(piece);
+    assert_eq!(2, board.clear_lines().len());
+    assert!(board.is_empty());
+}
+
+#[test]
+fn locked_pieces_comments_and_repeated_pages_are_decoded() {
+    // An L clears the row, then three pages show what is left of it.
+    let pages = fumen::decode("v115@bhG8MeSSJvhCAAPEAuoneEAAAAAA").unwrap();
+    assert_eq!(4, pages.len());
+    assert_eq!(Board::from_text("GGGGGGG...", 20, 10).unwrap().to_text(), pages[0].board.to_text());
+    assert_eq!(Some('L'), pages[0].piece.as_ref().map(|piece| piece.piece_type().letter()));
+    assert_eq!("", pages[0].comment);
+    for page in &pages[1..] {
+        assert_eq!(Board::from_text(".........L", 20, 10).unwrap().to_text(), page.board.to_text());
+        assert!(page.piece.is_none());
+        // Comments carry over to the pages without one.
+        assert_eq!("next", page.comment);
+    }
+}
+
+#[test]
+fn risen_garbage_leaves_an_empty_garbage_row() {
+    // The garbage row rises twice, the second time with nothing under it.
+    let pages = fumen::decode("v115@lhI8AeAYJvhBA4BAAA").unwrap();
+    let boards: Vec<String> = pages.iter().map(|page| page.board.to_text()).collect();
+    let expected: Vec<String> = ["", "GGGGGGGGG.", "GGGGGGGGG.\n.........."].iter()
+        .map(|text| Board::from_text(text, 20, 10).unwrap().to_text())
+        .collect();
+    assert_eq!(expected, boards);
+}
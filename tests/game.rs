@@ -0,0 +1,144 @@
+//! Rules of a single game: events, undo and redo.
+
+mod common;
+
+use common::lock;
+use tetris::attack::Classic;
+use tetris::board::Board;
+use tetris::game::Event;
+use tetris::game::Game;
+use tetris::mode;
+
+// Locks the piece and waits for the next one, the position undo goes back
+// to.
+fn play(game: &mut Game) {
+    lock(game);
+    while game.piece().is_none() {
+        game.tick();
+    }
+}
+
+fn free_play() -> Game {
+    let mut game = Game::with_seed(1);
+    game.allow_undo();
+    game.run();
+    game
+}
+
+#[test]
+fn events_are_only_collected_when_asked_for() {
+    let mut game = Game::with_seed(1);
+    game.run();
+    lock(&mut game);
+    assert!(game.take_events().is_empty());
+
+    game.record_events();
+    game.set_board(Board::from_text("GGGGGGGGG.", 20, 10).unwrap());
+    lock(&mut game);
+    let events = game.take_events();
+    assert_eq!(1, events.len());
+    assert!(matches!(events[0], Event::Lock {..}));
+    assert!(game.take_events().is_empty());
+}
+
+#[test]
+fn attack_is_counted_by_the_table_of_the_game() {
+    // Two lines, clearing the whole board.
+    let perfect_clear = |game: &mut Game| {
+        game.run();
+        common::o_over_the_middle(game, "GGGG..GGGG\nGGGG..GGGG");
+        lock(game);
+    };
+    let mut game = Game::with_seed(1);
+    perfect_clear(&mut game);
+    assert_eq!(1 + 10, game.attack());
+
+    let mut game = Game::with_seed(1);
+    game.set_attack_table(Box::new(Classic));
+    perfect_clear(&mut game);
+    assert_eq!(1, game.attack());
+}
+
+#[test]
+fn nothing_is_undone_without_history() {
+    let mut game = free_play();
+    game.set_history_capacity(0);
+    play(&mut game);
+    game.undo();
+    assert_eq!(1, game.pieces());
+
+    game.set_history_capacity(1);
+    play(&mut game);
+    game.undo();
+    assert_eq!(1, game.pieces());
+}
+
+#[test]
+fn only_the_last_moves_are_undone() {
+    let mut game = free_play();
+    game.set_history_capacity(3);
+    for _ in 0..5 {
+        play(&mut game);
+    }
+    for _ in 0..5 {
+        game.undo();
+    }
+    assert_eq!(2, game.pieces());
+    for _ in 0..5 {
+        game.redo();
+    }
+    assert_eq!(5, game.pieces());
+}
+
+#[test]
+fn a_new_move_forgets_the_undone_ones() {
+    let mut game = free_play();
+    play(&mut game);
+    play(&mut game);
+    game.undo();
+    game.undo();
+    game.move_left();
+    play(&mut game);
+    let board = game.board().to_text();
+    game.redo();
+    assert_eq!(1, game.pieces());
+    assert_eq!(board, game.board().to_text());
+    game.undo();
+    assert_eq!(0, game.pieces());
+}
+
+#[test]
+fn only_practice_modes_and_free_play_undo() {
+    let undoes = |mut game: Game| {
+        game.run();
+        play(&mut game);
+        game.undo();
+        0 == game.pieces()
+    };
+    assert!(!undoes(Game::with_seed(1)));
+    let mut game = Game::with_seed(1);
+    game.allow_undo();
+    assert!(undoes(game));
+    for (name, allowed) in [("zen", true), ("practice", true), ("sprint", false), ("ultra", false), ("marathon", false)].iter() {
+        assert_eq!(*allowed, undoes(Game::with_mode(mode::from_name(name).unwrap())), "{}", name);
+    }
+}
+
+#[test]
+fn moves_are_undone_before_the_next_piece_spawns() {
+    let mut game = Game::with_mode(mode::from_name("zen").unwrap());
+    game.run();
+    lock(&mut game);
+    assert!(game.piece().is_none());
+    game.undo();
+    assert_eq!(0, game.pieces());
+    assert!(game.piece().is_some());
+    game.redo();
+    assert_eq!(1, game.pieces());
+    assert!(game.piece().is_some());
+
+    // Nothing to redo, the next piece keeps its delay.
+    lock(&mut game);
+    game.redo();
+    assert!(game.piece().is_none());
+}
@@ -0,0 +1,29 @@
+//! Ticks run by the fixed timestep for the time between frames.
+
+use tetris::game_loop::FixedTimestep;
+use tetris::game_loop::TICKS_PER_SECOND;
+
+#[test]
+fn ticks_follow_the_time_whatever_the_frame_rate() {
+    for &frames_per_second in [30., 60., 144.].iter() {
+        let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+        assert_eq!(0, timestep.advance(1000.));
+        let ticks: u32 = (1..=2 * frames_per_second as u32)
+            .map(|frame| timestep.advance(1000. + frame as f64 * 1000. / frames_per_second))
+            .sum();
+        // Two seconds, give or take the rounding of the last tick.
+        assert!((2 * TICKS_PER_SECOND - 1..=2 * TICKS_PER_SECOND).contains(&ticks), "{} at {} fps", ticks, frames_per_second);
+    }
+}
+
+#[test]
+fn long_frames_only_catch_up_on_a_quarter_of_a_second() {
+    let mut timestep = FixedTimestep::new(TICKS_PER_SECOND);
+    timestep.advance(0.);
+    // A tab back from the background after a minute.
+    assert_eq!(15, timestep.advance(60_000.));
+    assert_eq!(1, timestep.advance(60_017.));
+    // Clocks going back run nothing.
+    assert_eq!(0, timestep.advance(50_000.));
+    assert_eq!(1, timestep.advance(50_017.));
+}
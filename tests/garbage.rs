@@ -0,0 +1,50 @@
+//! Garbage waiting to rise: its delay and how attack cancels it.
+
+use tetris::garbage::GarbageQueue;
+
+#[test]
+fn attack_cancels_the_oldest_garbage_first() {
+    // Batches pushed, attack used to cancel, attack left, lines pending.
+    let cases: [(&[u32], u32, u32, u32); 6] = [
+        (&[], 3, 3, 0),
+        (&[3], 0, 0, 3),
+        (&[3], 2, 0, 1),
+        (&[3, 2], 4, 0, 1),
+        (&[3, 2], 5, 0, 0),
+        (&[3, 2], 7, 2, 0),
+    ];
+    for (batches, attack, left, pending) in cases.iter() {
+        let mut queue = GarbageQueue::new(0);
+        for &lines in batches.iter() {
+            queue.push(lines);
+        }
+        assert_eq!((*left, *pending), (queue.cancel(*attack), queue.pending_lines()), "{:?} {}", batches, attack);
+    }
+
+    let mut queue = GarbageQueue::new(0);
+    queue.push(3);
+    queue.push(2);
+    queue.cancel(4);
+    assert_eq!(vec![1], queue.take_ready());
+}
+
+#[test]
+fn garbage_rises_once_its_delay_is_over() {
+    let mut queue = GarbageQueue::new(2);
+    queue.push(0);
+    assert_eq!(0, queue.pending_lines());
+    queue.push(3);
+    queue.tick();
+    queue.push(1);
+    assert_eq!((4, 0), (queue.pending_lines(), queue.ready_lines()));
+    assert!(queue.take_ready().is_empty());
+
+    queue.tick();
+    assert_eq!((4, 3), (queue.pending_lines(), queue.ready_lines()));
+    assert_eq!(vec![3], queue.take_ready());
+    assert_eq!(1, queue.pending_lines());
+
+    queue.tick();
+    assert_eq!(vec![1], queue.take_ready());
+    assert_eq!(0, queue.pending_lines());
+}
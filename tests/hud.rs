@@ -0,0 +1,50 @@
+//! Sidebar values, written again only when they change.
+
+mod common;
+
+use tetris::game::Delays;
+use tetris::game::Game;
+use tetris::hud::Field;
+use tetris::hud::Hud;
+
+fn fields(changes: &[(Field, String)]) -> Vec<Field> {
+    changes.iter().map(|&(field, _)| field).collect()
+}
+
+#[test]
+fn everything_is_shown_first_then_only_changes() {
+    let mut game = Game::with_seed(5);
+    game.run();
+    let mut hud = Hud::new();
+    let changes = hud.update(&game);
+    assert_eq!(
+        vec![Field::Score, Field::Lines, Field::Level, Field::Time, Field::PiecesPerSecond, Field::AttackPerMinute],
+        fields(&changes),
+    );
+    assert_eq!((Field::Level, "1".to_string()), changes[2]);
+    assert!(hud.update(&game).is_empty());
+
+    game.tick();
+    assert_eq!(vec![(Field::Time, "0:00.016".to_string())], hud.update(&game));
+}
+
+#[test]
+fn clears_update_the_counters_and_the_attack() {
+    let mut game = Game::with_seed(5);
+    game.set_delays(Delays::NONE);
+    game.run();
+    for _ in 0..60 {
+        game.tick();
+    }
+    let mut hud = Hud::new();
+    hud.update(&game);
+
+    common::o_over_the_middle(&mut game, "G.........\nGGGG..GGGG\nGGGG..GGGG");
+    common::lock(&mut game);
+    assert_eq!(1, game.attack());
+    let changes = hud.update(&game);
+    assert_eq!(vec![Field::Score, Field::Lines, Field::PiecesPerSecond, Field::AttackPerMinute], fields(&changes));
+    assert_eq!((Field::Lines, "2".to_string()), changes[1]);
+    // One line sent in a second.
+    assert_eq!((Field::AttackPerMinute, "60.0".to_string()), changes[3]);
+}
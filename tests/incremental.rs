@@ -0,0 +1,99 @@
+//! Frames drawn again only where they changed, checked with a renderer
+//! recording what reaches it.
+
+mod common;
+
+use common::game_with_board;
+use common::Call;
+use common::RecordingRenderer;
+use tetris::draw;
+use tetris::draw::Renderer;
+use tetris::game::Game;
+use tetris::incremental::IncrementalRenderer;
+
+use std::collections::BTreeSet;
+
+// Draws a frame and returns what reached the renderer for it.
+fn draw(game: &Game, renderer: &mut IncrementalRenderer<RecordingRenderer>) -> Vec<Call> {
+    let start = renderer.renderer().calls.len();
+    draw::draw_board(game, renderer);
+    renderer.renderer().calls[start..].to_vec()
+}
+
+fn piece_cells(game: &Game) -> BTreeSet<(usize, usize)> {
+    let mut renderer = RecordingRenderer::default();
+    renderer.piece(game.piece().unwrap(), 0, 0);
+    renderer.calls.iter()
+        .filter_map(|call| match call {
+            Call::Cell {row, column, ..} => Some((*row, *column)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn first_frame_is_drawn_in_full() {
+    let game = game_with_board("GGGGGGGGG.");
+    let mut renderer = IncrementalRenderer::new(RecordingRenderer::default());
+    let calls = draw(&game, &mut renderer);
+    assert_eq!(Call::Clear {rows: 24, columns: 10}, calls[0]);
+    assert!(calls.contains(&Call::Grid));
+    assert!(calls.contains(&Call::HorizontalLine {row: 20}));
+    // Every cell of the board, the rows above it included, and the piece.
+    assert_eq!(240 + 4, calls.iter().filter(|call| matches!(call, Call::Cell {..})).count());
+    assert_eq!(Some(&Call::EndFrame), calls.last());
+}
+
+#[test]
+fn unchanged_frame_draws_nothing() {
+    let game = game_with_board("GGGGGGGGG.");
+    let mut renderer = IncrementalRenderer::new(RecordingRenderer::default());
+    draw(&game, &mut renderer);
+    assert_eq!(vec![Call::EndFrame], draw(&game, &mut renderer));
+}
+
+#[test]
+fn only_cells_the_piece_left_or_reached_are_drawn_again() {
+    let mut game = game_with_board("GGGGGGGGG.");
+    let mut renderer = IncrementalRenderer::new(RecordingRenderer::default());
+    draw(&game, &mut renderer);
+    let before = piece_cells(&game);
+    game.move_left();
+    let after = piece_cells(&game);
+
+    let calls = draw(&game, &mut renderer);
+    assert!(!calls.iter().any(|call| matches!(call, Call::Clear {..})));
+    let redrawn = RecordingRenderer {calls}.redrawn_cells();
+    assert_eq!(before.symmetric_difference(&after).cloned().collect::<BTreeSet<_>>(), redrawn);
+}
+
+#[test]
+fn changing_the_renderer_draws_the_whole_frame() {
+    let game = game_with_board("GGGGGGGGG.");
+    let mut renderer = IncrementalRenderer::new(RecordingRenderer::default());
+    draw(&game, &mut renderer);
+    renderer.renderer_mut();
+    assert_eq!(Call::Clear {rows: 24, columns: 10}, draw(&game, &mut renderer)[0]);
+}
+
+#[test]
+fn text_and_size_changes_draw_the_whole_frame() {
+    let mut game = game_with_board("GGGGGGGGG.");
+    let mut renderer = IncrementalRenderer::new(RecordingRenderer::default());
+    draw(&game, &mut renderer);
+
+    game.pause();
+    let calls = draw(&game, &mut renderer);
+    assert_eq!(Call::Clear {rows: 24, columns: 10}, calls[0]);
+    assert!(calls.iter().any(|call| matches!(call, Call::Text {..})));
+    assert_eq!(vec![Call::EndFrame], draw(&game, &mut renderer));
+
+    game.pause();
+    let calls = draw(&game, &mut renderer);
+    assert_eq!(Call::Clear {rows: 24, columns: 10}, calls[0]);
+    assert!(!calls.iter().any(|call| matches!(call, Call::Text {..})));
+
+    let start = renderer.renderer().calls.len();
+    draw::draw_next_pieces(&game, &mut renderer);
+    assert_eq!(Call::Clear {rows: 4, columns: 12}, renderer.renderer().calls[start]);
+}
@@ -0,0 +1,110 @@
+//! Delays between a lock and the next piece, and cleared rows flashing
+//! meanwhile.
+
+mod common;
+
+use common::lock;
+use tetris::draw;
+use tetris::game::Delays;
+use tetris::game::Game;
+use tetris::mode;
+use tetris::mode::Survival;
+use tetris::svg::SvgRenderer;
+
+const DELAYS: Delays = Delays {are: 3, line_are: 10};
+
+// An O piece about to fill the hole of the two bottom rows.
+fn game_about_to_clear(mut game: Game) -> Game {
+    game.run();
+    common::o_over_the_middle(&mut game, "GGGG..GGGG\nGGGG..GGGG");
+    game
+}
+
+#[test]
+fn next_piece_comes_after_the_entry_delay() {
+    let mut game = Game::with_seed(1);
+    game.set_delays(DELAYS);
+    game.run();
+    lock(&mut game);
+    for _ in 0..DELAYS.are - 1 {
+        assert!(game.piece().is_none());
+        game.tick();
+    }
+    assert!(game.line_clear().is_none());
+    game.tick();
+    assert!(game.piece().is_some());
+}
+
+#[test]
+fn cleared_rows_are_shown_until_the_line_entry_delay_ends() {
+    let mut game = Game::with_seed(1);
+    game.set_delays(DELAYS);
+    let mut game = game_about_to_clear(game);
+    lock(&mut game);
+
+    assert_eq!(2, game.lines());
+    assert!(game.board().is_empty());
+    let line_clear = game.line_clear().unwrap();
+    assert_eq!(&[0, 1], line_clear.rows());
+    assert_eq!("OO", line_clear.board().to_text().lines().last().unwrap().trim_matches('G'));
+    for _ in 0..DELAYS.line_are - 1 {
+        game.tick();
+        assert!(game.line_clear().is_some());
+        assert!(game.piece().is_none());
+    }
+    game.tick();
+    assert!(game.line_clear().is_none());
+    assert!(game.piece().is_some());
+}
+
+#[test]
+fn competitive_modes_have_no_delay() {
+    let mut game = game_about_to_clear(Game::with_mode(mode::from_name("sprint").unwrap()));
+    assert_eq!(Delays::NONE, game.delays());
+    lock(&mut game);
+    assert!(game.line_clear().is_none());
+    assert!(game.piece().is_some());
+
+    let game = Game::with_mode(mode::from_name("marathon").unwrap());
+    assert_eq!(Delays::default(), game.delays());
+}
+
+#[test]
+fn cleared_rows_flash() {
+    let mut game = Game::with_seed(1);
+    game.set_delays(DELAYS);
+    let mut game = game_about_to_clear(game);
+    lock(&mut game);
+
+    let white_cells = |game: &Game| {
+        let mut renderer = SvgRenderer::new(30);
+        draw::draw_board(game, &mut renderer);
+        renderer.finish().matches("height=\"30\" fill=\"white\"/>").count()
+    };
+    // Two rows lit, then back to the stack for as many frames.
+    assert_eq!(20, white_cells(&game));
+    for _ in 0..4 {
+        game.tick();
+    }
+    assert_eq!(0, white_cells(&game));
+    for _ in 0..4 {
+        game.tick();
+    }
+    assert_eq!(20, white_cells(&game));
+}
+
+#[test]
+fn survival_garbage_waits_for_the_cleared_rows_to_go() {
+    // A row due every 50 ms, that is every 3 ticks.
+    let mut game = game_about_to_clear(Game::with_mode(Box::new(Survival::new(50, 50, 1., 0.))));
+    lock(&mut game);
+    let mut ticks = 0;
+    while game.line_clear().is_some() {
+        game.tick();
+        ticks += 1;
+        assert_eq!(0, game.board().garbage_row_count());
+    }
+    assert_eq!(Delays::default().line_are, ticks);
+    game.tick();
+    assert_eq!(1, game.board().garbage_row_count());
+}
@@ -0,0 +1,198 @@
+//! What ends the game in each mode: goals reached, time out and top outs.
+
+mod common;
+
+use common::lock;
+use tetris::board::Board;
+use tetris::game::Game;
+use tetris::game::State;
+use tetris::mode;
+use tetris::mode::Dig;
+use tetris::mode::Survival;
+use tetris::mode::Ultra;
+
+fn start(name: &str) -> Game {
+    let mut game = Game::with_mode(mode::from_name(name).unwrap());
+    game.run();
+    game
+}
+
+// Two lines at once.
+fn double(game: &mut Game) {
+    common::o_over_the_middle(game, "GGGG..GGGG\nGGGG..GGGG");
+    lock(game);
+}
+
+// Fills the board but for one column, the next piece cannot come in.
+fn top_out(game: &mut Game) {
+    game.set_board(Board::from_text(&".GGGGGGGGG\n".repeat(20), 20, 10).unwrap());
+    game.move_down();
+}
+
+fn ticks(game: &mut Game, count: u32) {
+    for _ in 0..count {
+        game.tick();
+    }
+}
+
+// One "label: value" line per result.
+fn report(game: &Game) -> Vec<String> {
+    game.report().iter().map(|(label, value)| format!("{}: {}", label, value)).collect()
+}
+
+#[test]
+fn sprint_ends_on_the_fortieth_line_timed_to_the_tick() {
+    let mut game = start("sprint");
+    for _ in 0..19 {
+        double(&mut game);
+    }
+    assert!(State::Running == game.state());
+    ticks(&mut game, 3);
+    double(&mut game);
+    assert!(State::Finished == game.state());
+    assert_eq!(40, game.lines());
+    assert_eq!(50, game.elapsed_ms());
+    ticks(&mut game, 3);
+    assert_eq!(50, game.elapsed_ms());
+
+    let mut game = start("sprint");
+    top_out(&mut game);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn ultra_ends_when_the_time_is_up() {
+    let mut game = Game::with_mode(Box::new(Ultra::new(1000)));
+    game.run();
+    double(&mut game);
+    ticks(&mut game, 59);
+    assert!(State::Running == game.state());
+    game.tick();
+    assert!(State::Finished == game.state());
+    assert_eq!(1000, game.elapsed_ms());
+    assert_eq!(2, game.lines());
+
+    let mut game = start("ultra");
+    top_out(&mut game);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn sprint_reports_its_time_and_pace() {
+    let mut game = start("sprint");
+    ticks(&mut game, 3);
+    for _ in 0..20 {
+        double(&mut game);
+    }
+    assert_eq!(vec!("Mode: Sprint 40L", "Time: 0:00.050", "Lines: 40/40", "Pieces: 20", "PPS: 400.00"), report(&game));
+
+    assert!(Game::new().report().is_empty());
+}
+
+#[test]
+fn ultra_reports_what_was_done_in_its_time() {
+    let mut game = Game::with_mode(Box::new(Ultra::new(1000)));
+    game.run();
+    double(&mut game);
+    ticks(&mut game, 90);
+    assert_eq!(vec!("Mode: Ultra 0:01.000", "Time: 0:01.000", "Score: 2", "Lines: 2", "Attack: 11", "APM: 660.00", "PPS: 1.00"), report(&game));
+}
+
+#[test]
+fn marathon_levels_up_every_ten_lines_until_its_line_cap() {
+    let mut game = start("marathon");
+    assert_eq!(1, game.level());
+    for double_count in 1..75 {
+        double(&mut game);
+        assert_eq!(1 + double_count * 2 / 10, game.level());
+    }
+    assert!(State::Running == game.state());
+    double(&mut game);
+    assert!(State::Finished == game.state());
+    assert_eq!(150, game.lines());
+    assert_eq!(15, game.level());
+
+    let mut game = start("marathon");
+    top_out(&mut game);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn endless_marathon_only_ends_on_top_out() {
+    let mut game = start("endless");
+    for _ in 0..80 {
+        double(&mut game);
+    }
+    assert!(State::Running == game.state());
+    assert_eq!(17, game.level());
+    top_out(&mut game);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn dig_ends_once_its_garbage_is_cleared() {
+    let mut game = Game::with_mode(Box::new(Dig::new(3, 2, 0.)));
+    game.run();
+    assert_eq!(2, game.board().garbage_row_count());
+    lock(&mut game);
+    assert_eq!(2, game.board().garbage_row_count());
+
+    // Clearing the stack digs its garbage, counted at the next lock, and
+    // the board is topped up with what is left to send.
+    game.set_board(Board::new(20, 10));
+    lock(&mut game);
+    assert_eq!(1, game.board().garbage_row_count());
+    assert!(State::Running == game.state());
+    game.set_board(Board::new(20, 10));
+    lock(&mut game);
+    assert!(State::Finished == game.state());
+
+    let mut game = start("dig");
+    top_out(&mut game);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn dig_only_counts_the_garbage_it_sees_go() {
+    let mut game = Game::with_mode(Box::new(Dig::new(3, 2, 0.)));
+    game.run();
+    game.set_board(Board::from_text(&"G.GGGGGGGG\n".repeat(5), 20, 10).unwrap());
+    lock(&mut game);
+    assert!(State::Running == game.state());
+    assert_eq!(5, game.board().garbage_row_count());
+}
+
+#[test]
+fn survival_garbage_comes_faster_until_the_stack_tops_out() {
+    // At 100 ms, then every 50 ms: ticks 6, 9, 12 and so on.
+    let mut game = Game::with_mode(Box::new(Survival::new(100, 50, 0.5, 0.)));
+    game.run();
+    let mut rows = Vec::new();
+    for _ in 0..12 {
+        game.tick();
+        rows.push(game.board().garbage_row_count());
+    }
+    assert_eq!(vec![0, 0, 0, 0, 0, 1, 1, 1, 2, 2, 2, 3], rows);
+
+    // Three more rows push the stack out of the board.
+    game.set_board(Board::from_text(&".GGGGGGGGG\n".repeat(18), 20, 10).unwrap());
+    ticks(&mut game, 9);
+    assert!(State::Over == game.state());
+}
+
+#[test]
+fn zen_and_practice_only_empty_the_board_on_top_out() {
+    for &name in ["zen", "practice"].iter() {
+        let mut game = start(name);
+        top_out(&mut game);
+        assert!(State::Running == game.state(), "{}", name);
+        assert!(game.board().is_empty());
+        assert!(game.piece().is_some());
+    }
+
+    // Without gravity in practice.
+    let mut game = start("practice");
+    let row = game.piece().unwrap().row_offset();
+    ticks(&mut game, 600);
+    assert_eq!(row, game.piece().unwrap().row_offset());
+}
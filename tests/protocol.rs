@@ -0,0 +1,63 @@
+//! Packets of the match protocol, encoded and decoded back.
+
+use tetris::input::Input;
+use tetris::protocol::DecodeError;
+use tetris::protocol::Packet;
+
+fn packets() -> Vec<Packet> {
+    vec![
+        Packet::Join {room: "lobby".to_string(), name: "jérôme".to_string()},
+        Packet::Joined {player: 1},
+        Packet::Ready,
+        Packet::Start {seed: u64::MAX},
+        Packet::Inputs {player: 0, start_frame: 300, inputs: vec![], ack: 0},
+        Packet::Inputs {
+            player: 1,
+            start_frame: 70_000,
+            inputs: vec![Input::LEFT, Input::DOWN | Input::ROTATE, Input::NONE],
+            ack: 69_990,
+        },
+        Packet::KnockOut {player: 1},
+        Packet::Results {winner: 0},
+    ]
+}
+
+#[test]
+fn packets_decode_to_what_was_encoded() {
+    for packet in packets() {
+        assert_eq!(Ok(packet.clone()), Packet::decode(&packet.encode()));
+    }
+}
+
+#[test]
+fn truncated_packets_are_rejected() {
+    for packet in packets() {
+        let bytes = packet.encode();
+        for length in 0..bytes.len() {
+            assert_eq!(Err(DecodeError::UnexpectedEnd), Packet::decode(&bytes[..length]), "{:?} cut at {}", packet, length);
+        }
+    }
+}
+
+#[test]
+fn inputs_more_than_the_packet_holds_are_rejected() {
+    let mut bytes = Packet::Inputs {player: 0, start_frame: 0, inputs: vec![Input::LEFT; 4], ack: 0}.encode();
+    // The count of inputs, then two bytes of them.
+    let count = bytes.len() - 3;
+    bytes[count] = 5;
+    assert_eq!(Err(DecodeError::UnexpectedEnd), Packet::decode(&bytes));
+
+    // Inputs by the trillion, in a packet of a few bytes.
+    let mut bytes = vec![4, 0, 0, 0];
+    bytes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]);
+    bytes.push(0x11);
+    assert_eq!(Err(DecodeError::UnexpectedEnd), Packet::decode(&bytes));
+}
+
+#[test]
+fn unknown_tags_and_trailing_bytes_are_rejected() {
+    assert_eq!(Err(DecodeError::UnknownTag(42)), Packet::decode(&[42]));
+    let mut bytes = Packet::Ready.encode();
+    bytes.push(0);
+    assert_eq!(Err(DecodeError::TrailingBytes), Packet::decode(&bytes));
+}
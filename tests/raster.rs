@@ -0,0 +1,102 @@
+//! Pixel images of boards, and their PNG and GIF encodings read back.
+
+use tetris::board::Board;
+use tetris::game::Game;
+use tetris::input;
+use tetris::input::Input;
+use tetris::raster;
+use tetris::replay::Replay;
+use tetris::theme::Theme;
+
+const CYAN: [u8; 3] = [0x00, 0xFF, 0xFF];
+const GRID: [u8; 3] = [0xAA, 0xAA, 0xAA];
+const RED: [u8; 3] = [0xFF, 0x00, 0x00];
+const EMPTY: [u8; 3] = [0xDD, 0xDD, 0xDD];
+
+// Moves along with the frame, the same game after game.
+fn scripted_input(frame: usize) -> Input {
+    match frame % 20 {
+        0 => Input::LEFT,
+        7 => Input::ROTATE,
+        _ if 1 == frame % 2 => Input::DOWN,
+        _ => Input::NONE,
+    }
+}
+
+fn recorded_replay(frames: usize) -> (Replay, Game) {
+    let mut game = Game::with_seed(7);
+    game.run();
+    let mut replay = Replay::new(game.clone());
+    for frame in 0..frames {
+        input::apply(&mut game, scripted_input(frame));
+        game.tick();
+        replay.record(scripted_input(frame));
+    }
+    (replay, game)
+}
+
+#[test]
+fn cells_and_lines_land_on_their_pixels() {
+    let board = Board::from_text("IIII......", 20, 10).unwrap();
+    let image = raster::board_to_image(&board, None, false, &Theme::classic());
+    // 24 rows and 10 columns of 30 pixel cells, with lines between them.
+    assert_eq!((311, 745), (image.width(), image.height()));
+    assert_eq!(GRID, image.pixel(0, 0));
+    assert_eq!(CYAN, image.pixel(1, 714));
+    assert_eq!(CYAN, image.pixel(123, 743));
+    assert_eq!(GRID, image.pixel(124, 743));
+    assert_eq!(EMPTY, image.pixel(125, 743));
+    // Below the rows above the board.
+    assert_eq!(RED, image.pixel(40, 4 * 31));
+}
+
+#[test]
+fn png_decodes_to_the_image() {
+    let board = Board::from_text("ZZ.....OO.\nIIIIG..OO.", 20, 10).unwrap();
+    let image = raster::board_to_image(&board, None, false, &Theme::classic());
+    let png = raster::board_to_png(&board, None, false, &Theme::classic());
+
+    let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).unwrap();
+    assert_eq!((image.width() as u32, image.height() as u32), (info.width, info.height));
+    assert_eq!(png::ColorType::Rgb, info.color_type);
+    for y in 0..image.height() {
+        for x in 0..image.width() {
+            let offset = (y * image.width() + x) * 3;
+            assert_eq!(image.pixel(x, y), [data[offset], data[offset + 1], data[offset + 2]]);
+        }
+    }
+}
+
+#[test]
+fn replay_plays_the_recorded_game_again() {
+    let (replay, game) = recorded_replay(300);
+    assert_eq!(300, replay.frames());
+    assert!(game.pieces() >= 2);
+    let mut frames = 0;
+    let mut last_board = None;
+    replay.play(|game| {
+        frames += 1;
+        last_board = Some(game.board().to_text());
+    });
+    assert_eq!(301, frames);
+    assert_eq!(Some(game.board().to_text()), last_board);
+}
+
+#[test]
+fn gif_shows_every_few_frames_and_the_last_one() {
+    let (replay, _) = recorded_replay(31);
+    let gif = raster::replay_to_gif(&replay, 6, &Theme::classic());
+
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(gif.as_slice()).unwrap();
+    assert_eq!((311, 745), (decoder.width(), decoder.height()));
+    let mut delays = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        delays.push(frame.delay);
+    }
+    // Frames 0, 6, ... 30 for a tenth of a second, then frame 31.
+    assert_eq!(vec![10, 10, 10, 10, 10, 1, 10], delays);
+}
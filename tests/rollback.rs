@@ -0,0 +1,163 @@
+//! Rollback sessions played over the loopback transport, checked against a
+//! plain simulation of the same inputs.
+
+use std::collections::BTreeMap;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use tetris::attack::Guideline;
+use tetris::game::Game;
+use tetris::input::Input;
+use tetris::rollback::LinkConditions;
+use tetris::rollback::LoopbackTransport;
+use tetris::rollback::Rollback;
+use tetris::rollback::Simulation;
+use tetris::versus;
+use tetris::versus::Versus;
+
+const SEED: u64 = 42;
+const FRAMES: u32 = 600;
+const INPUT_DELAY: u32 = 2;
+const MAX_PREDICTION: u32 = 8;
+const FRAME_MS: f64 = 1000. / 60.;
+
+fn new_versus() -> Versus {
+    Versus::with_seed(Box::new(Guideline), versus::DEFAULT_GARBAGE_DELAY_TICKS, SEED)
+}
+
+fn random_input(rng: &mut StdRng) -> Input {
+    match rng.gen_range(0..10) {
+        0 => Input::LEFT,
+        1 => Input::RIGHT,
+        2 => Input::ROTATE,
+        3 | 4 => Input::DOWN,
+        _ => Input::NONE,
+    }
+}
+
+fn summary(game: &Game) -> (Vec<Vec<Option<u8>>>, u32, u32, u32) {
+    let cells = game.board().cells().iter()
+        .map(|row| row.iter().map(|cell| cell.0.map(|piece_type| piece_type as u8)).collect())
+        .collect();
+    (cells, game.score(), game.lines(), game.pieces())
+}
+
+fn assert_same_versus(actual: &Versus, expected: &Versus) {
+    for player in 0..2 {
+        assert!(summary(actual.game(player)) == summary(expected.game(player)), "player {} diverged", player);
+    }
+}
+
+fn replay(inputs: &[BTreeMap<u32, Input>; 2], frames: u32) -> Versus {
+    let mut versus = new_versus();
+    versus.run();
+    for frame in 0..frames {
+        let frame_inputs = [
+            inputs[0].get(&frame).copied().unwrap_or(Input::NONE),
+            inputs[1].get(&frame).copied().unwrap_or(Input::NONE),
+        ];
+        versus.advance(&frame_inputs);
+    }
+    versus
+}
+
+fn play_over_loopback(conditions: LinkConditions) -> u32 {
+    let (transport_0, transport_1) = LoopbackTransport::pair(conditions, SEED);
+    let mut sessions = [(transport_0, 0), (transport_1, 1)].map(|(transport, player)| {
+        let mut versus = new_versus();
+        versus.run();
+        Rollback::new(versus, transport, player, INPUT_DELAY, MAX_PREDICTION)
+    });
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut played_inputs = [BTreeMap::new(), BTreeMap::new()];
+
+    for _ in 0..FRAMES * 4 {
+        if sessions.iter().all(|session| session.frame() >= FRAMES) {
+            break;
+        }
+        sessions[0].transport_mut().advance_time(FRAME_MS);
+        for (player, session) in sessions.iter_mut().enumerate() {
+            if session.frame() >= FRAMES {
+                session.poll();
+                continue;
+            }
+            let input = random_input(&mut rng);
+            *played_inputs[player].entry(session.frame() + INPUT_DELAY).or_insert(Input::NONE) |= input;
+            session.add_local_input(input);
+            session.advance();
+        }
+    }
+    for _ in 0..1000 {
+        if sessions.iter().all(|session| session.confirmed_frames() >= FRAMES) {
+            break;
+        }
+        sessions[0].transport_mut().advance_time(FRAME_MS);
+        for session in sessions.iter_mut() {
+            session.poll();
+        }
+    }
+
+    let expected = replay(&played_inputs, FRAMES);
+    for session in sessions.iter() {
+        assert_eq!(FRAMES, session.frame());
+        assert!(session.confirmed_frames() >= FRAMES);
+        assert_same_versus(session.state(), &expected);
+    }
+    sessions.iter().map(|session| session.rollbacks()).sum()
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_versus() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut inputs = [BTreeMap::new(), BTreeMap::new()];
+    for frame in 0..FRAMES {
+        inputs[0].insert(frame, random_input(&mut rng));
+        inputs[1].insert(frame, random_input(&mut rng));
+    }
+    assert_same_versus(&replay(&inputs, FRAMES), &replay(&inputs, FRAMES));
+}
+
+#[test]
+fn perfect_link_never_rolls_back() {
+    let rollbacks = play_over_loopback(LinkConditions {latency_ms: 0., jitter_ms: 0., loss: 0.});
+    assert_eq!(0, rollbacks);
+}
+
+#[test]
+fn late_inputs_are_rolled_back_to_the_same_state() {
+    let rollbacks = play_over_loopback(LinkConditions {latency_ms: 60., jitter_ms: 0., loss: 0.});
+    assert!(rollbacks > 0);
+}
+
+#[test]
+fn jitter_and_loss_end_in_the_same_state() {
+    play_over_loopback(LinkConditions {latency_ms: 80., jitter_ms: 40., loss: 0.2});
+}
+
+#[test]
+fn confirmed_state_only_has_the_frames_both_inputs_are_known_for() {
+    let conditions = LinkConditions {latency_ms: 60., jitter_ms: 0., loss: 0.};
+    let (transport_0, transport_1) = LoopbackTransport::pair(conditions, SEED);
+    let mut sessions = [(transport_0, 0), (transport_1, 1)].map(|(transport, player)| {
+        let mut versus = new_versus();
+        versus.run();
+        Rollback::new(versus, transport, player, INPUT_DELAY, MAX_PREDICTION)
+    });
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut played_inputs = [BTreeMap::new(), BTreeMap::new()];
+    for _ in 0..120 {
+        sessions[0].transport_mut().advance_time(FRAME_MS);
+        for (player, session) in sessions.iter_mut().enumerate() {
+            let input = random_input(&mut rng);
+            *played_inputs[player].entry(session.frame() + INPUT_DELAY).or_insert(Input::NONE) |= input;
+            session.add_local_input(input);
+            session.advance();
+        }
+    }
+
+    for session in sessions.iter() {
+        assert!(session.confirmed_frames() < session.frame());
+        assert_same_versus(session.confirmed_state(), &replay(&played_inputs, session.confirmed_frames()));
+    }
+}
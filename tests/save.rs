@@ -0,0 +1,78 @@
+//! Saved games going on exactly like the games they were saved from.
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use tetris::game::Game;
+use tetris::game::State;
+use tetris::input;
+use tetris::input::Input;
+use tetris::save;
+
+fn play(game: &mut Game, rng: &mut StdRng, ticks: u32) {
+    for _ in 0..ticks {
+        let input = match rng.gen_range(0..8) {
+            0 => Input::LEFT,
+            1 => Input::RIGHT,
+            2 => Input::ROTATE,
+            3 | 4 => Input::DOWN,
+            _ => Input::NONE,
+        };
+        input::apply(game, input);
+        game.tick();
+    }
+}
+
+fn summary(game: &Game) -> (String, Vec<char>, u32, u32, u32, u64) {
+    let next_pieces = game.next_pieces().iter().map(|piece| piece.piece_type().letter()).collect();
+    (game.board().to_text(), next_pieces, game.score(), game.lines(), game.pieces(), game.elapsed_ms())
+}
+
+#[test]
+fn loaded_game_plays_on_like_the_saved_one() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut game = Game::with_seed(7);
+    game.run();
+    play(&mut game, &mut rng, 600);
+    assert!(State::Running == game.state());
+    // Dealt pieces, next ones included, go past the first bag, which the
+    // generator deals again on load.
+    assert!(game.pieces() >= 4);
+
+    let mut loaded = save::load(&save::save(&game)).unwrap();
+    assert!(State::Paused == loaded.state());
+    assert_eq!(summary(&game), summary(&loaded));
+
+    loaded.pause();
+    let mut loaded_rng = rng.clone();
+    play(&mut game, &mut rng, 600);
+    play(&mut loaded, &mut loaded_rng, 600);
+    assert_eq!(summary(&game), summary(&loaded));
+}
+
+#[test]
+fn other_versions_are_not_loaded() {
+    let mut game = Game::with_seed(7);
+    game.run();
+    let text = save::save(&game).replacen(&format!("\"version\":{}", save::VERSION), "\"version\":0", 1);
+    assert!(matches!(save::load(&text), Err(save::LoadError::UnsupportedVersion(0))));
+    assert!(matches!(save::load("{}"), Err(save::LoadError::Invalid(_))));
+}
+
+#[test]
+fn saves_no_game_could_have_written_are_not_loaded() {
+    let mut game = Game::with_seed(7);
+    game.run();
+    let text = save::save(&game);
+    assert!(save::load(&text).is_ok());
+
+    let wider = text.replacen("\"width\":10", "\"width\":12", 1);
+    assert!(matches!(save::load(&wider), Err(save::LoadError::WrongBoardSize {height: 20, width: 12})));
+    let shorter = text.replacen("\"height\":20", "\"height\":16", 1);
+    assert!(matches!(save::load(&shorter), Err(save::LoadError::WrongBoardSize {height: 16, width: 10})));
+
+    let bags = text.find("\"bags\":").unwrap() + "\"bags\":".len();
+    let digits = text[bags..].find(|character: char| !character.is_ascii_digit()).unwrap();
+    let endless = format!("{}{}{}", &text[..bags], u32::MAX, &text[bags + digits..]);
+    assert!(matches!(save::load(&endless), Err(save::LoadError::Invalid(_))));
+}
@@ -0,0 +1,45 @@
+//! SVG images of boards.
+
+use tetris::board::Board;
+use tetris::piece::Piece;
+use tetris::piece::Type;
+use tetris::svg;
+use tetris::theme::Theme;
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+#[test]
+fn document_fits_the_board_and_the_rows_above_it() {
+    let svg = svg::board_to_svg(&Board::new(20, 10), None, false, &Theme::classic());
+    // 24 rows and 10 columns of 30 pixel cells, with lines between them.
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"311\" height=\"745\""));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(24 * 10, count(&svg, "fill=\"#DDD\""));
+    assert_eq!(1, count(&svg, "stroke=\"#F00\""));
+}
+
+#[test]
+fn cells_use_the_colors_of_the_game() {
+    let board = Board::from_text("ZZ........\nIIIIG.....", 20, 10).unwrap();
+    let svg = svg::board_to_svg(&board, None, false, &Theme::classic());
+    assert_eq!(2, count(&svg, "fill=\"red\""));
+    assert_eq!(4, count(&svg, "fill=\"cyan\""));
+    assert_eq!(1, count(&svg, "fill=\"gray\""));
+    // The bottom left cell, one pixel in from the lines around it.
+    assert!(svg.contains("<rect x=\"1\" y=\"714\" width=\"30\" height=\"30\" fill=\"cyan\"/>"));
+}
+
+#[test]
+fn ghost_is_drawn_only_when_asked_for() {
+    let board = Board::new(20, 10);
+    let piece = Piece::new(16, 3, Type::T);
+    let without_ghost = svg::board_to_svg(&board, Some(&piece), false, &Theme::classic());
+    assert_eq!(4, count(&without_ghost, "fill=\"purple\""));
+    assert_eq!(0, count(&without_ghost, "fill=\"#888\""));
+
+    let with_ghost = svg::board_to_svg(&board, Some(&piece), true, &Theme::classic());
+    assert_eq!(4, count(&with_ghost, "fill=\"purple\""));
+    assert_eq!(4, count(&with_ghost, "fill=\"#888\""));
+}
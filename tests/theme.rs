@@ -0,0 +1,112 @@
+//! Themes, built in or read from JSON, and how boards are drawn with them.
+
+use tetris::board::Board;
+use tetris::piece::Piece;
+use tetris::piece::Type;
+use tetris::raster;
+use tetris::svg;
+use tetris::theme;
+use tetris::theme::CellStyle;
+use tetris::theme::GhostStyle;
+use tetris::theme::Theme;
+
+#[test]
+fn classic_theme_has_the_colors_the_game_always_had() {
+    let theme = Theme::classic();
+    let types = [Type::I, Type::T, Type::O, Type::L, Type::J, Type::S, Type::Z, Type::Garbage];
+    let colors: Vec<&str> = types.iter().map(|&piece_type| theme.cell_color(&tetris::piece::Cell(Some(piece_type)))).collect();
+    assert_eq!(vec!("cyan", "purple", "yellow", "orange", "blue", "lime", "red", "gray"), colors);
+    assert_eq!("#DDD", theme.cell_color(&tetris::piece::Cell(None)));
+    assert_eq!("#888", theme.ghost);
+    assert_eq!(("#F00", "orange"), (theme.garbage_ready.as_str(), theme.garbage_pending.as_str()));
+    assert_eq!(Theme::classic(), Theme::default());
+}
+
+#[test]
+fn built_in_themes_are_found_by_name_and_survive_json() {
+    for &name in ["classic", "guideline", "high-contrast", "deuteranopia", "protanopia", "tritanopia"].iter() {
+        let theme = theme::from_name(name).unwrap();
+        assert_eq!(theme, Theme::from_json(&theme.to_json()).unwrap());
+    }
+    assert_eq!(None, theme::from_name("neon"));
+}
+
+#[test]
+fn json_themes_change_only_the_fields_they_have() {
+    let theme = Theme::from_json(r##"{
+        "background": "black",
+        "pieces": {"I": "#0FF"},
+        "ghost_style": "piece-outline",
+        "cell_style": "beveled"
+    }"##).unwrap();
+    assert_eq!("black", theme.background);
+    assert_eq!("#0FF", theme.pieces.get(Type::I));
+    assert_eq!("purple", theme.pieces.get(Type::T));
+    assert_eq!(GhostStyle::PieceOutline, theme.ghost_style);
+    assert_eq!(CellStyle::Beveled, theme.cell_style);
+    assert_eq!(Theme::classic().grid, theme.grid);
+
+    assert!(Theme::from_json(r#"{"cell_style": "wobbly"}"#).is_err());
+}
+
+#[test]
+fn images_are_drawn_with_the_theme() {
+    let board = Board::from_text("IIII......", 20, 10).unwrap();
+    let image = raster::board_to_image(&board, None, false, &Theme::high_contrast());
+    // Outlined cells: the edge in the piece color, the inside left black.
+    assert_eq!([0x00, 0xFF, 0xFF], image.pixel(1, 714));
+    assert_eq!([0x00, 0x00, 0x00], image.pixel(16, 729));
+    assert_eq!([0x00, 0x00, 0x00], image.pixel(140, 729));
+
+    let guideline = Theme::guideline();
+    let piece = Piece::new(16, 3, Type::T);
+    let svg = svg::board_to_svg(&Board::new(20, 10), Some(&piece), true, &guideline);
+    assert!(svg.contains("fill=\"black\"/>"));
+    assert_eq!(8, svg.matches("\"#A000F0\"").count());
+    assert_eq!(4, svg.matches("fill=\"none\" stroke=\"#A000F0\"").count());
+}
+
+#[test]
+fn color_blind_palettes_have_a_color_and_a_glyph_per_piece() {
+    let types = [Type::I, Type::T, Type::O, Type::L, Type::J, Type::S, Type::Z, Type::Garbage];
+    for theme in [Theme::deuteranopia(), Theme::protanopia(), Theme::tritanopia()].iter() {
+        assert!(theme.glyphs);
+        let mut colors: Vec<&str> = types.iter().map(|&piece_type| theme.pieces.get(piece_type)).collect();
+        colors.sort();
+        colors.dedup();
+        assert_eq!(types.len(), colors.len());
+    }
+    let glyphs: Vec<char> = types.iter().filter_map(|&piece_type| theme::glyph(piece_type)).collect();
+    assert_eq!(vec!['I', 'T', 'O', 'L', 'J', 'S', 'Z'], glyphs);
+}
+
+#[test]
+fn glyphs_are_drawn_inside_the_cells() {
+    let board = Board::from_text("T.........", 20, 10).unwrap();
+    let theme = Theme::deuteranopia();
+    let svg = svg::board_to_svg(&board, None, false, &theme);
+    assert_eq!(1, svg.matches(">T</text>").count());
+    assert_eq!(0, svg_without_glyphs(&board).matches("</text>").count());
+
+    let image = raster::board_to_image(&board, None, false, &theme);
+    // The bar of the T, three pixels per font pixel, centered in the cell.
+    assert_eq!([0x00, 0x00, 0x00], image.pixel(8, 719));
+    assert_eq!([0xCC, 0x79, 0xA7], image.pixel(8, 727));
+    assert_eq!([0x00, 0x00, 0x00], image.pixel(16, 727));
+}
+
+fn svg_without_glyphs(board: &Board) -> String {
+    let theme = Theme {glyphs: false, ..Theme::deuteranopia()};
+    svg::board_to_svg(board, None, false, &theme)
+}
+
+#[test]
+fn json_themes_only_have_colors_images_can_be_drawn_with() {
+    assert!(Theme::from_json(r##"{"background": "#123456", "grid": "#abc", "text": "orange"}"##).is_ok());
+    for &color in ["teal", "#12345", "#GGG", "rgb(0, 0, 0)", ""].iter() {
+        let json = format!(r#"{{"pieces": {{"T": "{}"}}}}"#, color);
+        assert!(Theme::from_json(&json).is_err(), "{}", color);
+    }
+    assert_eq!(Some([0x11, 0x22, 0x33]), theme::rgb("#123"));
+    assert_eq!(Some([0xAB, 0xCD, 0xEF]), theme::rgb("#abcdef"));
+}
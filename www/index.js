@@ -1,4 +1,18 @@
 import "./style.css";
-import { run } from "tetris";
+import { has_saved_game, run, run_online, run_versus } from "tetris";
 
-run();
+const params = new URLSearchParams(window.location.search);
+const mode = params.get("mode");
+if (mode === "versus") {
+  run_versus(params.get("attack"), params.get("theme"));
+} else if (mode === "online") {
+  run_online(
+    params.get("server") || "ws://127.0.0.1:9001",
+    params.get("room") || "lobby",
+    params.get("name") || "player",
+    params.get("theme"),
+  );
+} else {
+  const resume = has_saved_game() && window.confirm("Resume the previous game?");
+  run(mode, params.get("fumen"), resume, params.get("theme"));
+}